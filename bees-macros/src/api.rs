@@ -0,0 +1,420 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned};
+use syn::{Token, parse::{Parse, ParseStream}, spanned::Spanned};
+
+use crate::{endpoint::path_params, handler_stacks::{HandlerBody, handler_stack}};
+
+// #[bees::api(..)] on a trait: its arguments go to the generated Record, every method with a verb
+// attribute becomes an Endpoint, and the trait is implemented on a `<Trait>Client` wrapper
+pub(crate) fn api_impl(args: TokenStream, mut input: syn::ItemTrait) -> syn::Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(input.generics.span(), "#[bees::api] traits can't be generic"));
+    }
+
+    let vis = input.vis.clone();
+    let trait_ident = input.ident.clone();
+    let record = format_ident!("{}Record", trait_ident);
+    let wrapper = format_ident!("{}Client", trait_ident);
+
+    let mut endpoints = TokenStream::new();
+    let mut methods = TokenStream::new();
+
+    for item in &mut input.items {
+        let syn::TraitItem::Fn(method) = item else {
+            continue;
+        };
+
+        let Some(verb) = take_verb(&mut method.attrs)? else {
+            // methods with a body are left to the trait
+            if method.default.is_some() {
+                continue;
+            }
+
+            return Err(syn::Error::new(
+                method.sig.ident.span(),
+                "#[bees::api] methods need a #[get], #[post], #[put], #[patch], #[delete], #[head] or #[options] attribute",
+            ));
+        };
+
+        let api_method = ApiMethod::parse(&mut method.sig)?;
+        let handlers = take_handlers(&mut method.attrs, &verb, &api_method.output)?;
+        let endpoint = format_ident!("{}{}", trait_ident, pascal_case(&method.sig.ident.to_string()));
+
+        endpoints.extend(api_method.endpoint(&vis, &record, &endpoint, &verb, &handlers)?);
+        methods.extend(api_method.implementation(&endpoint, &handlers)?);
+
+        // the trait's own signature: a Send future, like the one the wrapper returns
+        let output = &api_method.output;
+        method.sig.asyncness = None;
+        method.sig.output = syn::parse_quote! {
+            -> impl ::std::future::Future<Output = #output> + ::std::marker::Send
+        };
+    }
+
+    Ok(quote! {
+        #input
+
+        #[derive(::bees::Record)]
+        #[record(#args)]
+        #vis struct #record;
+
+        #endpoints
+
+        #[derive(Debug, Clone)]
+        #vis struct #wrapper {
+            client: ::bees::net::Client,
+        }
+
+        impl #wrapper {
+            #vis fn new(client: ::bees::net::Client) -> Self {
+                Self { client }
+            }
+
+            #vis fn client(&self) -> &::bees::net::Client {
+                &self.client
+            }
+        }
+
+        impl ::std::convert::From<::bees::net::Client> for #wrapper {
+            fn from(client: ::bees::net::Client) -> Self {
+                Self::new(client)
+            }
+        }
+
+        impl #trait_ident for #wrapper {
+            #methods
+        }
+    })
+}
+
+// #[get("path", <more #[endpoint] arguments>)]
+struct Verb {
+    verb: syn::Ident,
+    args: VerbArgs,
+}
+
+struct VerbArgs {
+    path: syn::LitStr,
+    // handed to #[endpoint] as they are
+    rest: TokenStream,
+    // picked out of `rest`, since they decide the method's output
+    response: Option<syn::Type>,
+    error: Option<syn::Type>,
+}
+
+impl Parse for VerbArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse::<syn::LitStr>()?;
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+
+        let rest = input.fork().parse::<TokenStream>()?;
+        let (mut response, mut error) = (None, None);
+
+        while !input.is_empty() {
+            let key = input.parse::<syn::Ident>()?;
+            input.parse::<Token![=]>()?;
+
+            // types can have commas in them, which an Expr wouldn't get past
+            match key.to_string().as_str() {
+                "response" => response = Some(input.parse::<syn::Type>()?),
+                "error" => error = Some(input.parse::<syn::Type>()?),
+                "record" | "modify_url" | "context" => {
+                    input.parse::<syn::Type>()?;
+                }
+                _ => {
+                    input.parse::<syn::Expr>()?;
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(Self { path, rest, response, error })
+    }
+}
+
+fn take_verb(attrs: &mut Vec<syn::Attribute>) -> syn::Result<Option<Verb>> {
+    const VERBS: [&str; 7] = ["get", "post", "put", "patch", "delete", "head", "options"];
+
+    let Some(i) = attrs.iter().position(|attr| VERBS.iter().any(|verb| attr.path().is_ident(verb))) else {
+        return Ok(None);
+    };
+
+    let attr = attrs.remove(i);
+    let ident = attr.path().get_ident().expect("checked above");
+    let verb = Verb {
+        verb: syn::Ident::new(&ident.to_string().to_uppercase(), ident.span()),
+        args: attr.parse_args::<VerbArgs>()?,
+    };
+
+    if let Some(other) = attrs.iter().find(|attr| VERBS.iter().any(|verb| attr.path().is_ident(verb))) {
+        return Err(syn::Error::new(other.span(), "a #[bees::api] method can only have one verb attribute"));
+    }
+
+    Ok(Some(verb))
+}
+
+// where a method's output comes from
+enum MethodStack {
+    // #[handlers(..)] (same as the right side of #[stacks])
+    Handlers(HandlerBody),
+    // `response = T, error = E` in the verb attribute, whose HandlerStack the Endpoint derive writes
+    Json { response: syn::Type, error: syn::Type },
+}
+
+impl MethodStack {
+    fn output(&self) -> syn::Result<TokenStream> {
+        match self {
+            MethodStack::Handlers(handlers) => {
+                let handlers = handlers.handlers_type()?;
+                Ok(quote! { <#handlers as ::bees::handlers::Handler>::Output })
+            }
+            MethodStack::Json { response, error } => Ok(quote! {
+                ::std::result::Result<#response, ::bees::provided::handlers::ResponseError<#error>>
+            }),
+        }
+    }
+}
+
+fn take_handlers(attrs: &mut Vec<syn::Attribute>, verb: &Verb, output: &syn::Type) -> syn::Result<MethodStack> {
+    if let Some(i) = attrs.iter().position(|attr| attr.path().is_ident("handlers")) {
+        return Ok(MethodStack::Handlers(attrs.remove(i).parse_args::<HandlerBody>()?));
+    }
+
+    match (&verb.args.response, &verb.args.error) {
+        (Some(response), Some(error)) => Ok(MethodStack::Json { response: response.clone(), error: error.clone() }),
+        // the same stack, for the T and E of the method's own `Result<T, E>`
+        (None, None) => {
+            let Some((response, error)) = result_types(output) else {
+                return Err(syn::Error::new(
+                    output.span(),
+                    "a #[bees::api] method needs to return a `Result<T, E>`, or have `response = .., error = ..` or a #[handlers(..)] attribute",
+                ));
+            };
+
+            let body = quote! {
+                ::bees::handlers::BaseHandler, ::bees::provided::handlers::TypedJson<#response, #error>
+            };
+            Ok(MethodStack::Handlers(syn::parse2(body)?))
+        }
+        // the Endpoint derive complains about a lone `response` or `error`
+        _ => Ok(MethodStack::Handlers(syn::parse2(quote! { ::bees::handlers::BaseHandler })?)),
+    }
+}
+
+// T and E out of a return type spelled `Result<T, E>`
+fn result_types(ty: &syn::Type) -> Option<(&syn::Type, &syn::Type)> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+
+    let segment = path.path.segments.last().filter(|segment| segment.ident == "Result")?;
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+
+    match (args.args.first()?, args.args.get(1)?) {
+        (syn::GenericArgument::Type(response), syn::GenericArgument::Type(error)) if args.args.len() == 2 => {
+            Some((response, error))
+        }
+        _ => None,
+    }
+}
+
+struct ApiArg {
+    // as it's written in the signature, minus the field attributes
+    arg: syn::PatType,
+    ident: syn::Ident,
+    field_ty: syn::Type,
+    // turns the argument into the field
+    to_field: TokenStream,
+    attrs: Vec<syn::Attribute>,
+}
+
+struct ApiMethod {
+    ident: syn::Ident,
+    args: Vec<ApiArg>,
+    output: syn::Type,
+}
+
+impl ApiMethod {
+    // also strips the field attributes off the signature's arguments
+    fn parse(sig: &mut syn::Signature) -> syn::Result<Self> {
+        if !sig.generics.params.is_empty() {
+            return Err(syn::Error::new(sig.generics.span(), "#[bees::api] methods can't be generic"));
+        }
+
+        let output = match &sig.output {
+            syn::ReturnType::Type(_, ty) => (**ty).clone(),
+            syn::ReturnType::Default => {
+                return Err(syn::Error::new(sig.span(), "#[bees::api] methods need to return a Result"));
+            }
+        };
+
+        let mut inputs = sig.inputs.iter_mut();
+        match inputs.next() {
+            Some(syn::FnArg::Receiver(receiver)) if receiver.reference.is_some() && receiver.mutability.is_none() => {}
+            _ => return Err(syn::Error::new(sig.ident.span(), "#[bees::api] methods take `&self`")),
+        }
+
+        let mut args = Vec::new();
+        for input in inputs {
+            let syn::FnArg::Typed(arg) = input else {
+                unreachable!("only the first argument can be a receiver");
+            };
+
+            let syn::Pat::Ident(syn::PatIdent { ident, .. }) = &*arg.pat else {
+                return Err(syn::Error::new(arg.pat.span(), "#[bees::api] arguments need to be plain names"));
+            };
+
+            let (field_ty, to_field) = owned(&arg.ty, ident);
+            let ident = ident.clone();
+            let attrs = std::mem::take(&mut arg.attrs);
+            args.push(ApiArg { arg: arg.clone(), ident, field_ty, to_field, attrs });
+        }
+
+        Ok(Self { ident: sig.ident.clone(), args, output })
+    }
+
+    fn endpoint(
+        &self,
+        vis: &syn::Visibility,
+        record: &syn::Ident,
+        endpoint: &syn::Ident,
+        verb: &Verb,
+        handlers: &MethodStack,
+    ) -> syn::Result<TokenStream> {
+        let Verb { verb, args: VerbArgs { path, rest, .. } } = verb;
+        let placeholders = path_params(path)?;
+
+        // arguments named after a placeholder fill it in, the others are query parameters
+        // unless they say otherwise
+        let fields = self.args.iter().map(|ApiArg { ident, field_ty, attrs, .. }| {
+            let role = match attrs.is_empty() {
+                false => quote! {},
+                true if placeholders.contains(ident) => quote! { #[path_param] },
+                true => quote! { #[query] },
+            };
+
+            quote! { #(#attrs)* #role #vis #ident: #field_ty }
+        });
+
+        let body = match self.args.is_empty() {
+            true => quote! { ; },
+            false => quote! { { #(#fields),* } },
+        };
+
+        let endpoint_name = endpoint.to_string();
+        let stack = match handlers {
+            MethodStack::Handlers(body) => handler_stack(endpoint, handlers.output()?, body)?,
+            MethodStack::Json { .. } => quote! {},
+        };
+
+        Ok(quote! {
+            #[derive(::bees::Endpoint)]
+            #[endpoint(
+                record = #record,
+                path = #path,
+                http_method = ::bees::net::HttpMethod::new_no_body(::bees::net::HttpVerb::#verb),
+                #rest
+            )]
+            #vis struct #endpoint #body
+
+            // the arguments might not be Debug, and headers might be secret
+            impl ::std::fmt::Debug for #endpoint {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    f.debug_struct(#endpoint_name).finish_non_exhaustive()
+                }
+            }
+
+            #stack
+        })
+    }
+
+    fn implementation(&self, endpoint: &syn::Ident, handlers: &MethodStack) -> syn::Result<TokenStream> {
+        let Self { ident, args, output } = self;
+        let handlers_output = handlers.output()?;
+
+        let inputs = args.iter().map(|ApiArg { arg, .. }| arg);
+
+        let context = match args.is_empty() {
+            true => quote! { () },
+            false => {
+                let fields = args.iter().map(|ApiArg { ident, to_field, .. }| quote! { #ident: #to_field });
+                quote! { #endpoint { #(#fields),* } }
+            }
+        };
+
+        let span = output.span();
+        Ok(quote_spanned! {span=>
+            #[allow(clippy::manual_async_fn)]
+            fn #ident(&self, #(#inputs),*) -> impl ::std::future::Future<Output = #output> + ::std::marker::Send {
+                let context = #context;
+                async move {
+                    ::bees::api::ApiOutput::from_run(
+                        self.client.run_endpoint_with::<#endpoint, #handlers_output>(context).await
+                    )
+                }
+            }
+        })
+    }
+}
+
+// the fields have to own their values, so borrowed arguments are turned into their owned types
+fn owned(ty: &syn::Type, ident: &syn::Ident) -> (syn::Type, TokenStream) {
+    if let syn::Type::Reference(reference) = ty {
+        return (owned_type(&reference.elem), quote! { ::std::borrow::ToOwned::to_owned(#ident) });
+    }
+
+    if let Some(syn::Type::Reference(reference)) = option_inner(ty) {
+        let inner = owned_type(&reference.elem);
+        return (
+            syn::parse_quote! { ::std::option::Option<#inner> },
+            quote! { #ident.map(::std::borrow::ToOwned::to_owned) },
+        );
+    }
+
+    (ty.clone(), quote! { #ident })
+}
+
+// spelled out for str and slices, so that the Endpoint derive still sees a String or a Vec
+fn owned_type(ty: &syn::Type) -> syn::Type {
+    match ty {
+        syn::Type::Path(path) if path.path.is_ident("str") => syn::parse_quote! { ::std::string::String },
+        syn::Type::Slice(slice) => {
+            let elem = &slice.elem;
+            syn::parse_quote! { Vec<#elem> }
+        }
+        ty => syn::parse_quote! { <#ty as ::std::borrow::ToOwned>::Owned },
+    }
+}
+
+fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+
+    let segment = path.path.segments.last().filter(|segment| segment.ident == "Option")?;
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+
+    match args.args.first()? {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
+
+fn pascal_case(snake: &str) -> String {
+    snake
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect()
+}
@@ -1,6 +1,6 @@
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
-use syn::{Expr, Token, parse::Parse, punctuated::Punctuated};
+use syn::{Token, parse::Parse, punctuated::Punctuated};
 
 pub(crate) fn chain_impl(chain: Chain) -> syn::Result<TokenStream> {
     chain.tokenize()
@@ -33,6 +33,7 @@ pub(crate) fn pipe_impl(Pipe { members }: Pipe) -> syn::Result<TokenStream> {
         ));
     }
 
+    let (second_to_last, last) = (pipe_value(&second_to_last), pipe_value(&last));
     let mut tokens = if first_try_token.is_some() {
         quote! { ::bees::handlers::TryChain(#second_to_last, #last) }
     } else {
@@ -40,6 +41,7 @@ pub(crate) fn pipe_impl(Pipe { members }: Pipe) -> syn::Result<TokenStream> {
     };
 
     for ChainMember { ty, try_token } in iter {
+        let ty = pipe_value(&ty);
         if try_token.is_some() {
             tokens = quote! { ::bees::handlers::TryChain(#ty, #tokens) };
         } else {
@@ -50,6 +52,35 @@ pub(crate) fn pipe_impl(Pipe { members }: Pipe) -> syn::Result<TokenStream> {
     Ok(tokens)
 }
 
+// handlers named by their type are built with Default, so that aliases like IntoJson (which
+// can't be used as a value, unlike a unit struct) work too. every provided handler derives it
+pub(crate) fn handler_value(ty: &syn::Type) -> TokenStream {
+    match ty {
+        syn::Type::Path(_) => quote! { <#ty as ::std::default::Default>::default() },
+        _ => quote! { #ty },
+    }
+}
+
+// the same for pipe!, where a bare `IntoJson` is parsed as a path expression. only paths that
+// look like a type are touched, so locals, consts and enum variants are left alone
+fn pipe_value(expr: &syn::Expr) -> TokenStream {
+    let syn::Expr::Path(syn::ExprPath { qself: None, path, .. }) = expr else {
+        return quote! { #expr };
+    };
+
+    let mut names = path.segments.iter().rev().map(|segment| is_type_name(&segment.ident));
+    match (names.next(), names.next()) {
+        (Some(true), None | Some(false)) => quote! { <#path as ::std::default::Default>::default() },
+        _ => quote! { #expr },
+    }
+}
+
+// UpperCamelCase, but not a SCREAMING_CASE const
+fn is_type_name(ident: &syn::Ident) -> bool {
+    let name = ident.to_string();
+    name.starts_with(|c: char| c.is_ascii_uppercase()) && name.chars().any(|c| c.is_ascii_lowercase())
+}
+
 fn wrap_in_chain(ty: &syn::Type, to_wrap: &mut TokenStream) {
     *to_wrap = quote! { ::bees::handlers::Chain<#ty, #to_wrap> }
 }
@@ -185,6 +216,7 @@ impl Chain {
             ));
         }
 
+        let (second_to_last, last) = (handler_value(second_to_last), handler_value(last));
         let mut tokens = if first_try_token.is_some() {
             quote! { ::bees::handlers::TryChain(#second_to_last, #last) }
         } else {
@@ -192,6 +224,7 @@ impl Chain {
         };
 
         for ChainMember { ty, try_token } in iter {
+            let ty = handler_value(ty);
             if try_token.is_some() {
                 tokens = quote! { ::bees::handlers::TryChain(#ty, #tokens) }
             } else {
@@ -201,10 +234,6 @@ impl Chain {
 
         Ok(tokens)
     }
-
-    pub(crate) unsafe fn new_raw(members: Punctuated<ChainMember<syn::Type>, Token![,]>) -> Self {
-        Self { members }
-    }
 }
 
 pub(crate) struct Pipe {
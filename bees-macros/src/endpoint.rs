@@ -2,7 +2,7 @@ use deluxe::ParseAttributes;
 use quote::{quote, quote_spanned};
 use syn::{spanned::Spanned};
 
-use crate::record::make_capabilities;
+use crate::record::{make_capabilities, make_rate_limiter_fn};
 
 pub(crate) fn endpoint_derive(input: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let EndpointAttributes {
@@ -13,8 +13,39 @@ pub(crate) fn endpoint_derive(input: syn::DeriveInput) -> syn::Result<proc_macro
         path,
         // handler: HandlerSpec {block, output, ..},
         modify_url,
+        rate_limit,
+        cost,
+        priority,
+        idempotent,
+        context,
+        response,
+        error,
     } = EndpointAttributes::parse_attributes(&input)?;
 
+    let fields = RequestFields::parse(&input.data)?;
+    let path_params = path_params(&path)?;
+
+    if !fields.is_empty() {
+        if let Some(context) = &context {
+            return Err(syn::Error::new(
+                context.span(),
+                "an Endpoint with #[query], #[header], #[path_param] or #[body] fields is its own context",
+            ));
+        }
+        fields.check_path_params(&path, &path_params)?;
+    } else if !path_params.is_empty() && context.is_none() {
+        return Err(syn::Error::new(
+            path.span(),
+            "this path has `{..}` placeholders, so it needs a `context = ..` (or #[path_param] fields) to take their values from",
+        ));
+    }
+
+    let context = match (context, fields.is_empty()) {
+        (Some(context), _) => quote! { #context },
+        (None, false) => quote! { Self },
+        (None, true) => quote! { () },
+    };
+
     let ident = input.ident;
     let ident_span = ident.span();
     let impl_piece = quote_spanned! {ident_span=> 
@@ -29,17 +60,52 @@ pub(crate) fn endpoint_derive(input: syn::DeriveInput) -> syn::Result<proc_macro
     let path_piece = quote_spanned! {path_span=> const PATH: &str = #path; };
 
     let http_verb_span = http_verb.span();
-    let http_verb_piece = quote_spanned! {http_verb_span=> 
-        #[allow(clippy::manual_async_fn)]
-        fn http_method(_: &mut Self::CallContext) -> impl Future<Output = HttpMethod> + Send { async move { #http_verb } } 
+    let http_verb_piece = match &fields.body {
+        // serialized up front, so that the future doesn't borrow the context
+        Some((field, kind)) => {
+            let constructor = syn::Ident::new(kind.constructor(), field.span());
+            quote_spanned! {http_verb_span=>
+                #[allow(clippy::manual_async_fn)]
+                fn http_method(ctx: &mut Self::CallContext) -> impl ::std::future::Future<Output = ::bees::net::HttpMethod> + ::std::marker::Send {
+                    let body = ::bees::provided::capabilities::fields::PreparedBody::#constructor(&ctx.#field);
+                    async move {
+                        let mut method = #http_verb;
+                        method.body = ::std::option::Option::Some(::bees::net::bodies::Body::new(body));
+                        method
+                    }
+                }
+            }
+        }
+        None => quote_spanned! {http_verb_span=> 
+            #[allow(clippy::manual_async_fn)]
+            fn http_method(_: &mut Self::CallContext) -> impl ::std::future::Future<Output = ::bees::net::HttpMethod> + ::std::marker::Send { async move { #http_verb } } 
+        },
     };
 
-    let capability_pieces = make_capabilities(capabilities);
+    let mut capability_pieces = make_capabilities(capabilities).collect::<Vec<_>>();
+
+    let headers = fields.headers.iter().map(|(field, name, kind)| {
+        kind.collect(field, name, quote! { headers })
+    }).collect::<Vec<_>>();
 
-    let path = quote! { ::bees::capability::Capability };
+    if !headers.is_empty() {
+        capability_pieces.push(quote! {
+            ::std::boxed::Box::new({
+                let mut headers = ::std::vec::Vec::<(&'static str, ::std::string::String)>::new();
+                #(#headers)*
+                ::bees::provided::capabilities::fields::FieldHeaders(headers)
+            }) as ::std::boxed::Box<dyn ::bees::capability::Capability>
+        });
+    }
+
+    let capability_trait = quote! { ::bees::capability::Capability };
+    let ctx_arg = match headers.is_empty() {
+        true => quote! { _ },
+        false => quote! { ctx },
+    };
 
     let capability_fn = quote! {
-        fn capabilities(_: &mut Self::CallContext) -> ::std::sync::Arc<[Box<dyn #path>]> {
+        fn capabilities(#ctx_arg: &mut Self::CallContext) -> ::std::sync::Arc<[Box<dyn #capability_trait>]> {
             ::std::sync::Arc::new([ #(#capability_pieces),* ])
         }
     };
@@ -60,11 +126,80 @@ pub(crate) fn endpoint_derive(input: syn::DeriveInput) -> syn::Result<proc_macro
         },
     };
 
-    let url_mod_fn = quote! {
-        #[allow(clippy::manual_async_fn)]
-        fn modify_url(____url___: ::bees::re_exports::url::Url, _: &mut Self::CallContext) -> impl ::std::future::Future<Output = ::bees::re_exports::url::Url> + ::std::marker::Send {
-            #url_mod_fn_body
+    let queries = fields.queries.iter().map(|(field, name, kind)| {
+        kind.collect(field, name, quote! { query })
+    }).collect::<Vec<_>>();
+
+    let url_mod_fn = match queries.is_empty() {
+        true => quote! {
+            #[allow(clippy::manual_async_fn)]
+            fn modify_url(____url___: ::bees::re_exports::url::Url, _: &mut Self::CallContext) -> impl ::std::future::Future<Output = ::bees::re_exports::url::Url> + ::std::marker::Send {
+                #url_mod_fn_body
+            }
+        },
+        // query fields go in before a modify_url fn sees the url
+        false => quote! {
+            #[allow(clippy::manual_async_fn)]
+            fn modify_url(mut ____url___: ::bees::re_exports::url::Url, ctx: &mut Self::CallContext) -> impl ::std::future::Future<Output = ::bees::re_exports::url::Url> + ::std::marker::Send {
+                let mut query = ::std::vec::Vec::<(&'static str, ::std::string::String)>::new();
+                #(#queries)*
+                // so that a url doesn't get a lone `?` when every query field is None
+                if !query.is_empty() {
+                    ____url___.query_pairs_mut().extend_pairs(query);
+                }
+
+                #url_mod_fn_body
+            }
+        },
+    };
+
+    let rate_limiter_fn = make_rate_limiter_fn(rate_limit);
+
+    let cost_fn = match cost {
+        Some(cost) => {
+            let cost_span = cost.span();
+            quote_spanned! {cost_span=>
+                #[allow(unused_variables)]
+                fn cost(ctx: &mut Self::CallContext) -> u64 { #cost }
+            }
         }
+        None => quote! {},
+    };
+
+    // field accesses spanned to the path, so a placeholder without a matching field is an error there
+    let path_params_fn = match path_params.is_empty() {
+        true => quote! {},
+        false => {
+            let path_span = path.span();
+            let names = path_params.iter().map(|param| param.to_string());
+            quote_spanned! {path_span=>
+                fn path_params(ctx: &mut Self::CallContext) -> ::std::vec::Vec<(&'static str, ::std::string::String)> {
+                    ::std::vec![ #( (#names, ::std::string::ToString::to_string(&ctx.#path_params)) ),* ]
+                }
+            }
+        }
+    };
+
+    let priority_fn = match priority {
+        Some(priority) => {
+            let priority_span = priority.span();
+            quote_spanned! {priority_span=>
+                #[allow(unused_variables)]
+                fn priority(ctx: &mut Self::CallContext) -> ::bees::net::rate_limiter::Priority { #priority }
+            }
+        }
+        None => quote! {},
+    };
+
+    let idempotent_fn = match idempotent {
+        Some(idempotent) => {
+            let idempotent_span = idempotent.span();
+            quote_spanned! {idempotent_span=>
+                #[allow(unused_variables)]
+                fn idempotent(ctx: &mut Self::CallContext) -> ::std::option::Option<bool> { ::std::option::Option::Some(#idempotent) }
+            }
+        }
+        None => quote! {},
     };
 
     // let proc_impls = processors.into_iter().map(|proc_path| {
@@ -82,19 +217,29 @@ pub(crate) fn endpoint_derive(input: syn::DeriveInput) -> syn::Result<proc_macro
     //     }
     // });
 
+    let json_stack = json_stack(&ident, response, error)?;
+
     let result = quote! {
+        #json_stack
+
         #impl_piece {
             #path_piece
 
             // #handler_type_piece
             #record_piece
-            type CallContext = ();
+            type CallContext = #context;
 
             #http_verb_piece
             #capability_fn
 
             // #handler_expr_piece
             #url_mod_fn
+            #path_params_fn
+
+            #rate_limiter_fn
+            #cost_fn
+            #priority_fn
+            #idempotent_fn
         }
 
         // #(#proc_impls)*
@@ -113,4 +258,215 @@ struct EndpointAttributes {
     path: syn::LitStr,
     // handler: HandlerSpec,
     modify_url: Option<syn::Type>,
+    rate_limit: Option<syn::Expr>,
+    cost: Option<syn::Expr>,
+    priority: Option<syn::Expr>,
+    idempotent: Option<syn::Expr>,
+    context: Option<syn::Type>,
+    response: Option<syn::Type>,
+    error: Option<syn::Type>,
+}
+
+// #[endpoint(response = T, error = E)] gets a HandlerStack for Result<T, ResponseError<E>>
+fn json_stack(ident: &syn::Ident, response: Option<syn::Type>, error: Option<syn::Type>) -> syn::Result<proc_macro2::TokenStream> {
+    let (response, error) = match (response, error) {
+        (Some(response), Some(error)) => (response, error),
+        (None, None) => return Ok(quote! {}),
+        (Some(response), None) => return Err(syn::Error::new(response.span(), "`response` needs an `error` to decode non-2xx bodies into")),
+        (None, Some(error)) => return Err(syn::Error::new(error.span(), "`error` needs a `response` to decode 2xx bodies into")),
+    };
+
+    let handlers = quote! {
+        ::bees::handlers::Chain<::bees::handlers::BaseHandler, ::bees::provided::handlers::TypedJson<#response, #error>>
+    };
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl ::bees::endpoint::HandlerStack<::std::result::Result<#response, ::bees::provided::handlers::ResponseError<#error>>> for #ident {
+            type Handlers = #handlers;
+
+            async fn handlers(_: &mut <Self as ::bees::endpoint::EndpointInfo>::CallContext) -> ::std::result::Result<Self::Handlers, ::bees::endpoint::HandlerStackError> {
+                ::std::result::Result::Ok(::bees::handlers::Chain(::bees::handlers::BaseHandler, ::bees::provided::handlers::TypedJson::new()))
+            }
+        }
+    })
+}
+
+#[derive(Clone, Copy)]
+enum FieldKind {
+    Plain,
+    // skipped when None
+    Option,
+    // one entry per item
+    Vec,
+}
+
+impl FieldKind {
+    fn of(ty: &syn::Type) -> Self {
+        let syn::Type::Path(path) = ty else {
+            return FieldKind::Plain;
+        };
+
+        match path.path.segments.last().map(|s| s.ident.to_string()).as_deref() {
+            Some("Option") => FieldKind::Option,
+            Some("Vec") => FieldKind::Vec,
+            _ => FieldKind::Plain,
+        }
+    }
+
+    // pushes the field's (name, value) pairs onto `into`
+    fn collect(self, field: &syn::Ident, name: &syn::LitStr, into: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        let to_string = quote! { ::std::string::ToString::to_string };
+        match self {
+            FieldKind::Plain => quote! { #into.push((#name, #to_string(&ctx.#field))); },
+            FieldKind::Option => quote! {
+                if let ::std::option::Option::Some(value) = &ctx.#field {
+                    #into.push((#name, #to_string(value)));
+                }
+            },
+            FieldKind::Vec => quote! {
+                for value in &ctx.#field {
+                    #into.push((#name, #to_string(value)));
+                }
+            },
+        }
+    }
+}
+
+enum BodyKind {
+    Json,
+    Form,
+}
+
+impl BodyKind {
+    fn constructor(&self) -> &'static str {
+        match self {
+            BodyKind::Json => "json",
+            BodyKind::Form => "form",
+        }
+    }
+}
+
+// the fields of an Endpoint struct that describe its requests
+#[derive(Default)]
+struct RequestFields {
+    queries: Vec<(syn::Ident, syn::LitStr, FieldKind)>,
+    headers: Vec<(syn::Ident, syn::LitStr, FieldKind)>,
+    paths: Vec<syn::Ident>,
+    body: Option<(syn::Ident, BodyKind)>,
+}
+
+impl RequestFields {
+    fn parse(data: &syn::Data) -> syn::Result<Self> {
+        let mut fields = Self::default();
+
+        let syn::Data::Struct(syn::DataStruct { fields: syn::Fields::Named(named), .. }) = data else {
+            return Ok(fields);
+        };
+
+        for field in &named.named {
+            let ident = field.ident.clone().expect("named fields have idents");
+            let kind = FieldKind::of(&field.ty);
+
+            for attr in &field.attrs {
+                let Some(name) = attr.path().get_ident().map(ToString::to_string) else {
+                    continue;
+                };
+
+                match name.as_str() {
+                    // #[query] or #[query("name")]
+                    "query" => {
+                        let name = match &attr.meta {
+                            syn::Meta::Path(_) => syn::LitStr::new(&ident.to_string(), ident.span()),
+                            _ => attr.parse_args::<syn::LitStr>()?,
+                        };
+                        fields.queries.push((ident.clone(), name, kind));
+                    }
+                    // #[header("X-Name")]
+                    "header" => fields.headers.push((ident.clone(), attr.parse_args::<syn::LitStr>()?, kind)),
+                    "path_param" => {
+                        attr.meta.require_path_only()?;
+                        fields.paths.push(ident.clone());
+                    }
+                    // #[body], #[body(json)] or #[body(form)]
+                    "body" => {
+                        let body_kind = match &attr.meta {
+                            syn::Meta::Path(_) => BodyKind::Json,
+                            _ => {
+                                let kind = attr.parse_args::<syn::Ident>()?;
+                                match kind.to_string().as_str() {
+                                    "json" => BodyKind::Json,
+                                    "form" => BodyKind::Form,
+                                    _ => return Err(syn::Error::new(kind.span(), "expected `json` or `form`")),
+                                }
+                            }
+                        };
+
+                        if fields.body.is_some() {
+                            return Err(syn::Error::new(attr.span(), "an Endpoint can only have one #[body] field"));
+                        }
+                        fields.body = Some((ident.clone(), body_kind));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(fields)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queries.is_empty() && self.headers.is_empty() && self.paths.is_empty() && self.body.is_none()
+    }
+
+    // every placeholder needs a #[path_param] field, and every #[path_param] field a placeholder
+    fn check_path_params(&self, path: &syn::LitStr, params: &[syn::Ident]) -> syn::Result<()> {
+        if let Some(param) = params.iter().find(|param| !self.paths.contains(param)) {
+            return Err(syn::Error::new(path.span(), format!("`{{{param}}}` has no matching #[path_param] field")));
+        }
+
+        if let Some(field) = self.paths.iter().find(|field| !params.contains(field)) {
+            return Err(syn::Error::new(field.span(), format!("#[path_param] field `{field}` has no `{{{field}}}` in the path")));
+        }
+
+        Ok(())
+    }
+}
+
+// the names in a path's `{name}` placeholders; `{{` and `}}` are literal braces
+pub(crate) fn path_params(path: &syn::LitStr) -> syn::Result<Vec<syn::Ident>> {
+    let value = path.value();
+    let mut chars = value.chars().peekable();
+    let mut params = Vec::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(syn::Error::new(path.span(), "unclosed `{` in path (did you mean `{{`?)")),
+                    }
+                }
+
+                let mut ident = syn::parse_str::<syn::Ident>(&name).map_err(|_| {
+                    syn::Error::new(path.span(), format!("`{{{name}}}` isn't a valid path placeholder, it should name a field"))
+                })?;
+                ident.set_span(path.span());
+                params.push(ident);
+            }
+            '}' => return Err(syn::Error::new(path.span(), "unpaired `}` in path (did you mean `}}`?)")),
+            _ => {}
+        }
+    }
+
+    Ok(params)
 }
\ No newline at end of file
@@ -111,8 +111,14 @@ pub(crate) fn attr_handler(mut func: syn::ItemFn) -> syn::Result<TokenStream> {
         }
     };
 
+    // field-less handlers can be built from their type alone, e.g. as a Route's arm in #[stacks]
+    let derives = match quotes.is_empty() {
+        true => quote! { #[derive(Debug, Default)] },
+        false => quote! { #[derive(Debug)] },
+    };
+
     let finished = quote! {
-        #[derive(Debug)]
+        #derives
         #struct_whole
 
         #struct_impl 
@@ -3,7 +3,7 @@ use proc_macro2::{Span, TokenStream};
 use quote::{ToTokens, quote};
 use syn::{Block, Token, parse::{Parse, ParseStream, discouraged::Speculative}, punctuated::Punctuated, token};
 
-use crate::Chain;
+use crate::{Chain, chain::handler_value};
 
 pub(crate) fn handler_stacks_impl(input: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let FullSpec(stacks) = deluxe::parse_attributes(&input)?;
@@ -12,27 +12,8 @@ pub(crate) fn handler_stacks_impl(input: syn::DeriveInput) -> syn::Result<proc_m
 
     let mut final_tokens = TokenStream::new();
 
-    for HandlerSpec { block, handler_list: chain, output_type } in stacks {
-        let tokens_chain = chain.tokenize()?;
-
-        // let quote_impl = quote! { impl ::bees::endpoint::HandlerStack<<#tokens_chain as ::bees::handlers::Handler>::Output> for #ident };
-        let quote_impl = quote! { impl ::bees::endpoint::HandlerStack<#output_type> for #ident };
-        let handlers_type = quote! { type Handlers = #tokens_chain; };
-
-        let handlers = quote! {
-
-            async fn handlers(ctx: &mut <Self as ::bees::endpoint::EndpointInfo>::CallContext) -> Result<Self::Handlers, Box<dyn ::std::error::Error + Send + Sync>> {Ok(#block)}
-        };  
-
-
-        let composed = quote! {
-            #[automatically_derived]
-            #quote_impl {
-                #handlers_type
-
-                #handlers
-            }
-        };
+    for HandlerSpec { output_type, body } in stacks {
+        let composed = handler_stack(&ident, output_type.to_token_stream(), &body)?;
 
         final_tokens = quote! {
             #final_tokens
@@ -44,6 +25,28 @@ pub(crate) fn handler_stacks_impl(input: syn::DeriveInput) -> syn::Result<proc_m
     Ok(final_tokens)
 }
 
+pub(crate) fn handler_stack(ident: &syn::Ident, output_type: TokenStream, body: &HandlerBody) -> syn::Result<TokenStream> {
+    let HandlerBody { block, handler_list: chain } = body;
+    let tokens_chain = chain.tokenize()?;
+
+    let quote_impl = quote! { impl ::bees::endpoint::HandlerStack<#output_type> for #ident };
+    let handlers_type = quote! { type Handlers = #tokens_chain; };
+
+    let handlers = quote! {
+
+        async fn handlers(ctx: &mut <Self as ::bees::endpoint::EndpointInfo>::CallContext) -> Result<Self::Handlers, Box<dyn ::std::error::Error + Send + Sync>> {Ok(#block)}
+    };  
+
+    Ok(quote! {
+        #[automatically_derived]
+        #quote_impl {
+            #handlers_type
+
+            #handlers
+        }
+    })
+}
+
 struct FullSpec(Punctuated<HandlerSpec, Token![;]>);
 
 impl<'t, T: HasAttributes + std::fmt::Debug> ParseAttributes<'t, T> for FullSpec {
@@ -75,16 +78,35 @@ impl<'t, T: HasAttributes + std::fmt::Debug> ParseAttributes<'t, T> for FullSpec
 struct HandlerSpec {
     output_type: syn::Type,
     // _colon: Token![:],
-    block: Block,
-    // _arrow: Token![->],
-    handler_list: HandlerList,
+    body: HandlerBody,
 }
 
 impl Parse for HandlerSpec {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let output_type = input.parse::<syn::Type>()?;
         let _x = input.parse::<Token![:]>()?;
+        let body = input.parse::<HandlerBody>()?;
+
+        Ok(Self { output_type, body })
+    }
+}
 
+// what comes after the `Output:` in #[stacks], either `Handlers, ..` or `{ expr } -> Handlers, ..`
+#[derive(Debug)]
+pub(crate) struct HandlerBody {
+    block: Block,
+    // _arrow: Token![->],
+    handler_list: HandlerList,
+}
+
+impl HandlerBody {
+    pub(crate) fn handlers_type(&self) -> syn::Result<TokenStream> {
+        self.handler_list.tokenize()
+    }
+}
+
+impl Parse for HandlerBody {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
         let (chain, block): (HandlerList, Block);
     
         // try to collapse the block and the output
@@ -102,9 +124,7 @@ impl Parse for HandlerSpec {
     
         }
 
-        let s = Self { output_type, block, handler_list: chain };
-
-        Ok(s)
+        Ok(Self { block, handler_list: chain })
     }
 }
 
@@ -164,7 +184,7 @@ impl HandlerList {
 
     pub fn tokenize_pipely(&self) -> syn::Result<TokenStream> {
         match self {
-            HandlerList::Single(ty) => Ok(ty.to_token_stream()),
+            HandlerList::Single(ty) => Ok(handler_value(ty)),
             HandlerList::Chain(chain) => chain.tokenize_pipely(),
         }
     }
@@ -1,7 +1,7 @@
 use proc_macro::TokenStream;
 use syn::parse_macro_input;
 
-use crate::{chain::{Chain, Pipe, chain_impl, pipe_impl}, endpoint::endpoint_derive, handler::attr_handler, handler_stacks::handler_stacks_impl, record::record_impl};
+use crate::{api::api_impl, chain::{Chain, Pipe, chain_impl, pipe_impl}, endpoint::endpoint_derive, handler::attr_handler, handler_stacks::handler_stacks_impl, record::record_impl};
 
 // mod derive_process;
 mod handler;
@@ -9,6 +9,7 @@ mod record;
 mod endpoint;
 mod chain;
 mod handler_stacks;
+mod api;
 
 #[proc_macro_attribute]
 pub fn handler(_attrs: TokenStream, input: TokenStream) -> TokenStream {
@@ -20,6 +21,16 @@ pub fn handler(_attrs: TokenStream, input: TokenStream) -> TokenStream {
     }
 } 
 
+#[proc_macro_attribute]
+pub fn api(attrs: TokenStream, input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::ItemTrait);
+
+    match api_impl(attrs.into(), input) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
 #[proc_macro]
 pub fn chain(input: TokenStream) -> TokenStream {
     let chain = parse_macro_input!(input as Chain);
@@ -60,7 +71,8 @@ pub fn record(input: TokenStream) -> TokenStream {
     }
 }
 
-#[proc_macro_derive(Endpoint, attributes(endpoint))]
+// `path_param` rather than `path`, which is a built-in attribute that derive helpers may not shadow
+#[proc_macro_derive(Endpoint, attributes(endpoint, query, header, path_param, body))]
 pub fn endpoint(input: TokenStream) -> TokenStream {
     let input = syn::parse_macro_input!(input as syn::DeriveInput);
 
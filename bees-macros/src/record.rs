@@ -1,9 +1,10 @@
 use deluxe::ParseAttributes;
 use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
 
 pub(crate) fn record_impl(input: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     
-    let RecordArgs { path, capabilities } = RecordArgs::parse_attributes(&input)?;
+    let RecordArgs { path, parent, capabilities, rate_limit, max_in_flight, mirrors, host_strategy } = RecordArgs::parse_attributes(&input)?;
     
     let capabilities = capabilities.unwrap_or(Vec::new());
     
@@ -19,12 +20,51 @@ pub(crate) fn record_impl(input: syn::DeriveInput) -> syn::Result<proc_macro2::T
     };
     
     let shared_caps = make_capabilities(capabilities);
+    let rate_limiter_fn = make_rate_limiter_fn(rate_limit);
+    let concurrency_limiter_fn = make_concurrency_limiter_fn(max_in_flight);
+
+    let parent_fn = match parent {
+        Some(parent) => {
+            let span = parent.span();
+            quote_spanned! {span=>
+                fn parent() -> ::std::option::Option<::bees::record::RecordInfo> {
+                    ::std::option::Option::Some(::bees::record::RecordInfo::of::<#parent>())
+                }
+            }
+        }
+        None => quote! {},
+    };
+
+    let mirrors_fn = match mirrors {
+        Some(mirrors) => quote! {
+            fn mirrors() -> &'static [&'static str] {
+                &[ #(#mirrors),* ]
+            }
+        },
+        None => quote! {},
+    };
+
+    let host_strategy_fn = match host_strategy {
+        Some(strategy) => {
+            let span = strategy.span();
+            quote_spanned! {span=>
+                fn host_strategy() -> ::bees::net::hosts::HostStrategy { #strategy }
+            }
+        }
+        None => quote! {},
+    };
 
     let implementation = quote! {#impl_piece {
         #shared_url
         fn shared_caps() -> ::std::sync::Arc<[Box<dyn ::bees::capability::Capability>]> {
             ::std::sync::Arc::new([ #(#shared_caps),* ])
         } 
+
+        #parent_fn
+        #rate_limiter_fn
+        #concurrency_limiter_fn
+        #mirrors_fn
+        #host_strategy_fn
     }};
 
     Ok(implementation)
@@ -37,9 +77,44 @@ pub(crate) fn make_capabilities(capabilities: Vec<syn::Expr>) -> impl Iterator<I
     })
 }
 
+// a new limiter every call; the Client calls it once and keeps what it gets
+pub(crate) fn make_rate_limiter_fn(rate_limit: Option<syn::Expr>) -> proc_macro2::TokenStream {
+    match rate_limit {
+        Some(expr) => {
+            let span = expr.span();
+            quote_spanned! {span=>
+                fn rate_limiter() -> ::std::option::Option<::std::sync::Arc<::bees::net::rate_limiter::RateLimiter>> {
+                    ::std::option::Option::Some(::std::sync::Arc::new(#expr))
+                }
+            }
+        }
+        None => quote! {},
+    }
+}
+
+// same as make_rate_limiter_fn
+fn make_concurrency_limiter_fn(max_in_flight: Option<syn::Expr>) -> proc_macro2::TokenStream {
+    match max_in_flight {
+        Some(expr) => {
+            let span = expr.span();
+            quote_spanned! {span=>
+                fn concurrency_limiter() -> ::std::option::Option<::std::sync::Arc<::bees::net::concurrency::ConcurrencyLimiter>> {
+                    ::std::option::Option::Some(::std::sync::Arc::new(::bees::net::concurrency::ConcurrencyLimiter::new(#expr)))
+                }
+            }
+        }
+        None => quote! {},
+    }
+}
+
 #[derive(Debug, ParseAttributes)]
 #[deluxe(attributes(record))]
 struct RecordArgs {
     path: syn::LitStr,
-    capabilities: Option<Vec<syn::Expr>>
+    parent: Option<syn::Type>,
+    capabilities: Option<Vec<syn::Expr>>,
+    rate_limit: Option<syn::Expr>,
+    max_in_flight: Option<syn::Expr>,
+    mirrors: Option<Vec<syn::LitStr>>,
+    host_strategy: Option<syn::Expr>,
 }
@@ -147,6 +147,6 @@ impl HandlerStack<JsonOutput> for MyEndpoint {
     async fn handlers(
         _: &mut <Self as EndpointInfo>::CallContext,
     ) -> Result<Self::Handlers, HandlerStackError> {
-        Ok(::bees::handlers::TryChain(BaseHandler, IntoJson))
+        Ok(::bees::handlers::TryChain(<BaseHandler as ::std::default::Default>::default(), <IntoJson as ::std::default::Default>::default()))
     }
 }
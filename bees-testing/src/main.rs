@@ -5,31 +5,34 @@ use bees::{
     handlers::BaseHandler,
     net::{Client, HttpMethod, HttpVerb, net_error::NetError, rate_limiter::RateLimiter},
     pipe,
-    provided::{capabilities::add_headers::AddHeaders, handlers::IntoJson, resources::constant_res::ConstRes},
+    provided::{capabilities::add_headers::AddHeaders, handlers::{DecodeError, IntoJson}, resources::constant_res::ConstRes},
 };
-use reqwest::{Client as ReqClient, Response};
+use reqwest::Response;
 use serde_json::Value;
+use std::time::Duration;
 
 #[tokio::main]
 async fn main() {
     let rate_per_sec = 5.0;
     let burst = 2;
-    // create the bees client:
-    //                               specify the reqwest client...    ...and the rate limiter
-    let client = Client::new(ReqClient::new(), RateLimiter::new(rate_per_sec, burst));
-
-    // add a Resource to the Client's resource manager, making it
-    // available for interpolation into URLs and request bodies
-    client.resource_manager.add_resource(ConstRes::new("my_resource", "some_value") );
+    // create the bees client with its rate limiter...
+    let client = Client::builder()
+        .rate_limiter(RateLimiter::new(rate_per_sec, burst))
+        // ...a default timeout for every request...
+        .timeout(Duration::from_secs(30))
+        // ...and a Resource, making it available for interpolation into URLs and request bodies
+        .resource(ConstRes::new("my_resource", "some_value"))
+        .build()
+        .expect("Couldn't build the Client");
 
     // once the Endpoint and its HandlerStacks have been declared, the Endpoint can
     // be easily called by using the .run_endpoint<EndpointName, Output>() method on the Client
-    let string_output: String = client
+    let _string_output: String = client
         .run_endpoint::<MyEndpoint, String>()
         .await
         .expect("Couldn't execute request");
 
-    let json_output: JsonOutput = client
+    let _json_output: JsonOutput = client
         .run_endpoint::<MyEndpoint, JsonOutput>()
         .await
         .expect("Couldn't execute request");
@@ -82,7 +85,8 @@ pub async fn IntoText(#[input] resp: Result<Response, NetError>) -> String {
         .expect("Couldn't get text out of Response")
 }
 
-type JsonOutput = Result<Result<Value, serde_json::Error>, NetError>;
+// IntoJson decodes into any serde type; Value is the default
+type JsonOutput = Result<Result<Value, DecodeError>, NetError>;
 
 // Another HandlerStack implementation; this means that the Endpoint will also support
 // JsonOutput as an Output
@@ -0,0 +1,19 @@
+use crate::utils::error::Error;
+
+// how the return type of a #[bees::api] method is made from running its Endpoint: the
+// handlers' error and the errors from building the request both go through From
+pub trait ApiOutput<O>: Sized {
+    fn from_run(run: Result<O, Error>) -> Self;
+}
+
+impl<T, E, F> ApiOutput<Result<T, F>> for Result<T, E>
+where
+    E: From<F> + From<Error>,
+{
+    fn from_run(run: Result<Result<T, F>, Error>) -> Self {
+        match run {
+            Ok(output) => output.map_err(E::from),
+            Err(e) => Err(E::from(e)),
+        }
+    }
+}
@@ -7,7 +7,6 @@ use std::error::Error as StdError;
 
 #[cfg(not(feature = "async-trait"))]
 use std::pin::Pin;
-use std::sync::Arc;
 
 pub type CapError = Box<dyn StdError + Send + Sync>;
 
@@ -1,22 +1,21 @@
 use std::{
-    any::TypeId, convert::Infallible, error::Error as StdError, fmt::Debug, future::ready, str::FromStr, sync::{Arc, OnceLock}
+    any::TypeId, error::Error as StdError, fmt::Debug, future::ready, str::FromStr, sync::Arc
 };
 
-use dashmap::DashMap;
-use reqwest::Response;
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
 use url::Url;
 
 use super::net::net_error::NetError;
-use crate::{handlers::BaseHandler, net::HttpMethod, resources::resource_handler::ResourceManager, utils::error::Error};
+use crate::{net::{Client, HttpMethod, concurrency::ConcurrencyLimiter, rate_limiter::{Priority, RateLimiter}}, utils::error::Error};
 use crate::{
     capability::Capability,
     handlers::Handler,
-    record::Record,
-    utils::resource_string::ResourceString,
+    record::{Record, RecordInfo},
+    utils::resource_string::{FormatStringError, ResourceString},
 };
 
 pub trait EndpointInfo: Send + Debug + 'static {
-    type Record: Record;
+    type Record: Record + 'static;
     type CallContext: Send + Sync;
 
     const PATH: &str;
@@ -28,49 +27,186 @@ pub trait EndpointInfo: Send + Debug + 'static {
     fn modify_url(url: Url, ctx: &mut Self::CallContext) -> impl Future<Output = Url> + Send {
         ready(url)
     }
+
+    // values for the `{name}` placeholders in PATH, percent-encoded before they're put in
+    #[allow(unused_variables)]
+    fn path_params(ctx: &mut Self::CallContext) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+
+    // same as Record::rate_limiter, but only for this Endpoint
+    fn rate_limiter() -> Option<Arc<RateLimiter>> {
+        None
+    }
+
+    // how many tokens a call takes from each rate limiter it goes through (the Client's included),
+    // for apis that charge some calls more than others
+    #[allow(unused_variables)]
+    fn cost(ctx: &mut Self::CallContext) -> u64 {
+        1
+    }
+
+    // decides who goes first when calls queue up on a rate limiter; take it from the
+    // CallContext to prioritize individual calls
+    #[allow(unused_variables)]
+    fn priority(ctx: &mut Self::CallContext) -> Priority {
+        Priority::Normal
+    }
+
+    // whether a call can safely be sent more than once, which is what Retries goes by;
+    // None leaves it to the HttpVerb
+    #[allow(unused_variables)]
+    fn idempotent(ctx: &mut Self::CallContext) -> Option<bool> {
+        None
+    }
 }
 
 pub trait EndpointExt: EndpointInfo {
-    fn parsed_path(client: &Arc<ResourceManager>) -> &'static ResourceString;
-    fn record_capabilities() -> Arc<[Box<dyn Capability>]>;
+    fn parsed_path(client: &Client) -> Arc<ParsedPath>;
+    fn record_capabilities() -> Vec<Arc<[Box<dyn Capability>]>>;
+    fn rate_limiters(client: &Client) -> Vec<Arc<RateLimiter>>;
+    fn concurrency_limiter(client: &Client) -> Option<Arc<ConcurrencyLimiter>>;
     fn full_url(
-        res_manager: &Arc<ResourceManager>, 
+        client: &Client,
         ctx: &mut <Self as EndpointInfo>::CallContext,
     ) -> impl Future<Output = Result<Url, Error>> + Send;
 }
 
 impl<E: EndpointInfo + 'static> EndpointExt for E {
     
-    fn parsed_path(res_manager: &Arc<ResourceManager>) -> &'static ResourceString {
-        static CACHE: OnceLock<DashMap<TypeId, &'static ResourceString>> = OnceLock::new();
-        let cache = CACHE.get_or_init(DashMap::new);
-        
-        cache.entry(TypeId::of::<E>())
+    // cached per Client, since the parsed path is bound to the Client's ResourceManager
+    fn parsed_path(client: &Client) -> Arc<ParsedPath> {
+        client.path_cache()
+            .entry(TypeId::of::<E>())
             .or_insert_with(|| {
-                let mut record = Self::Record::SHARED_URL.trim_end_matches("/").to_string();
-                let endpoint = Self::PATH.trim_start_matches("/");
+                let mut record = client.base_url::<Self::Record>().trim_end_matches("/").to_string();
                 record.push('/');
-                record.push_str(endpoint);
 
-                let resource = ResourceString::new_res_manager(res_manager, record);
-                Box::leak(Box::new(resource))
+                Arc::new(ParsedPath::new(client, &record, Self::PATH.trim_start_matches("/")))
             })
             .value()
+            .clone()
     }
 
-    async fn full_url(res_manager: &Arc<ResourceManager>, ctx: &mut <Self as EndpointInfo>::CallContext) -> Result<Url, Error> {
-        let parsed = Self::parsed_path(res_manager);
-        let formatted = &parsed.to_formatted_now().await?;
-        println!("formatted: {formatted}");
+    async fn full_url(client: &Client, ctx: &mut <Self as EndpointInfo>::CallContext) -> Result<Url, Error> {
+        let parsed = Self::parsed_path(client);
+        let formatted = parsed.format(&Self::path_params(ctx)).await?;
+
         Ok(Self::modify_url(
-            Url::from_str(formatted).map_err(NetError::NotAValidUrl)?,
+            Url::from_str(&formatted).map_err(NetError::NotAValidUrl)?,
             ctx,
         )
         .await)
     }
 
-    fn record_capabilities() -> Arc<[Box<dyn Capability>]> {
-        <<Self as EndpointInfo>::Record as Record>::shared_caps()
+    // one set per Record, from the root Record down to this Endpoint's
+    fn record_capabilities() -> Vec<Arc<[Box<dyn Capability>]>> {
+        RecordInfo::of::<<Self as EndpointInfo>::Record>()
+            .chain()
+            .into_iter()
+            .map(|record| (record.shared_caps)())
+            .collect()
+    }
+
+    // made once per Client, like parsed_path
+    fn rate_limiters(client: &Client) -> Vec<Arc<RateLimiter>> {
+        let limiters = client.route_limiters();
+        let record = limiters
+            .records
+            .entry(TypeId::of::<<Self as EndpointInfo>::Record>())
+            .or_insert_with(<<Self as EndpointInfo>::Record as Record>::rate_limiter)
+            .clone();
+        let endpoint = limiters
+            .endpoints
+            .entry(TypeId::of::<E>())
+            .or_insert_with(<Self as EndpointInfo>::rate_limiter)
+            .clone();
+
+        record.into_iter().chain(endpoint).collect()
+    }
+
+    fn concurrency_limiter(client: &Client) -> Option<Arc<ConcurrencyLimiter>> {
+        client
+            .route_limiters()
+            .concurrency
+            .entry(TypeId::of::<<Self as EndpointInfo>::Record>())
+            .or_insert_with(<<Self as EndpointInfo>::Record as Record>::concurrency_limiter)
+            .clone()
+    }
+}
+
+// everything but unreserved characters, so a value can never leave its path segment
+const PATH_PARAM: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+// an Endpoint's url with PATH cut up at its `{name}` placeholders, so that only PATH's own
+// placeholders are filled in, and never anything a base url or a <resource> turns into
+#[derive(Debug)]
+pub struct ParsedPath {
+    base: ResourceString,
+    parts: Vec<PathPart>,
+}
+
+#[derive(Debug)]
+enum PathPart {
+    Text(ResourceString),
+    Param(String),
+}
+
+impl ParsedPath {
+    // `{{` and `}}` stand for literal braces, whether or not the path has placeholders
+    fn new(client: &Client, base: &str, path: &str) -> Self {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut rest = path;
+
+        while let Some(i) = rest.find(['{', '}']) {
+            text.push_str(&rest[..i]);
+            let brace = &rest[i..];
+
+            if brace.starts_with("{{") || brace.starts_with("}}") {
+                text.push_str(&brace[..1]);
+                rest = &brace[2..];
+                continue;
+            }
+
+            match brace.strip_prefix('{').and_then(|inner| inner.split_once('}')) {
+                Some((name, after)) => {
+                    parts.push(PathPart::Text(ResourceString::new(client, std::mem::take(&mut text))));
+                    parts.push(PathPart::Param(name.to_string()));
+                    rest = after;
+                }
+                None => {
+                    text.push_str(&brace[..1]);
+                    rest = &brace[1..];
+                }
+            }
+        }
+
+        text.push_str(rest);
+        parts.push(PathPart::Text(ResourceString::new(client, text)));
+
+        Self { base: ResourceString::new(client, base), parts }
+    }
+
+    // placeholders without a value are left alone
+    async fn format(&self, params: &[(&'static str, String)]) -> Result<String, FormatStringError> {
+        let mut formatted = self.base.to_formatted_now().await?;
+
+        for part in &self.parts {
+            match part {
+                PathPart::Text(text) => formatted.push_str(&text.to_formatted_now().await?),
+                PathPart::Param(name) => match params.iter().find(|(n, _)| n == name) {
+                    Some((_, value)) => formatted.extend(utf8_percent_encode(value, PATH_PARAM)),
+                    None => {
+                        formatted.push('{');
+                        formatted.push_str(name);
+                        formatted.push('}');
+                    }
+                },
+            }
+        }
+
+        Ok(formatted)
     }
 }
 
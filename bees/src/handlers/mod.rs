@@ -1,7 +1,11 @@
 use reqwest::Response;
 
-use crate::{net::{Request, net_error::NetError}, utils::error::Error};
-use std::{fmt::Debug, num::NonZeroUsize};
+use crate::net::{Request, net_error::NetError};
+use std::fmt::Debug;
+
+pub mod retry;
+
+pub use retry::*;
 
 // ######## TRAITS ########
 pub trait Handler: Debug + Send {
@@ -113,90 +117,101 @@ where
     
 // }
 
-// ######## BASE_HANDLER ########
-#[derive(Debug, Clone)]
-pub struct NoRateLimiterBaseHandler;
+// ######## STATUS ROUTING ########
+// sends a Response to H if its status is within LO..=HI, and to Next otherwise; nest them and end
+// with Otherwise, e.g. Route<200, 299, Decode, OnStatus<404, NotFound, Otherwise<Fail>>>. every
+// arm has to have the same Output
+#[derive(Debug, Clone, Default)]
+pub struct Route<const LO: u16, const HI: u16, H, Next>(pub H, pub Next);
 
-impl Handler for NoRateLimiterBaseHandler {
-    type Output = Result<reqwest::Response, NetError>;
-    type Input = Request;
+pub type OnStatus<const CODE: u16, H, Next> = Route<CODE, CODE, H, Next>;
+
+impl<const LO: u16, const HI: u16, H, Next> Handler for Route<LO, HI, H, Next>
+where
+    H: Handler<Input = Response> + Sync,
+    Next: Handler<Input = Response, Output = H::Output> + Sync,
+{
+    type Input = Response;
+
+    type Output = H::Output;
 
     async fn execute(
         &self,
-        req: Self::Input,
+        input: Self::Input,
     ) -> Self::Output {
-        req.client.execute_reqwest_req_no_rate_limit(req.inner).await
+        match (LO..=HI).contains(&input.status().as_u16()) {
+            true => self.0.execute(input).await,
+            false => self.1.execute(input).await,
+        }
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct BaseHandler;
+// the last arm of a Route, for every status that got this far
+#[derive(Debug, Clone, Default)]
+pub struct Otherwise<H>(pub H);
 
-impl Handler for BaseHandler {
-    type Output = Result<reqwest::Response, NetError>;
-    type Input = Request;
+impl<H: Handler<Input = Response> + Sync> Handler for Otherwise<H> {
+    type Input = Response;
+
+    type Output = H::Output;
 
     async fn execute(
         &self,
-        req: Self::Input,
+        input: Self::Input,
     ) -> Self::Output {
-        req.client.execute_reqwest_req(req.inner).await
+        self.0.execute(input).await
     }
 }
 
+// takes what BaseHandler gives and routes the Response, so that `BaseHandler, MatchStatus<..>`
+// is a whole stack; a NetError never reaches the arms
+#[derive(Debug, Clone, Default)]
+pub struct MatchStatus<R>(pub R);
 
+impl<R> Handler for MatchStatus<R>
+where
+    R: Handler<Input = Response> + Sync,
+    R::Output: Send,
+{
+    type Input = Result<Response, NetError>;
 
-// ######## RETRIES ########
-#[derive(Debug)]
-pub struct Retries<H: Handler + Sync> { inner: H, n_retries: NonZeroUsize }
+    type Output = Result<R::Output, NetError>;
 
-#[derive(Debug)]
-pub enum RetriesError<E> {
-    InnerError(E),
-    CouldNotCloneRequest,
+    async fn execute(
+        &self,
+        input: Self::Input,
+    ) -> Self::Output {
+        Ok(self.0.execute(input?).await)
+    }
 }
 
-impl<H, E> Retries<H> 
-where 
-    E: Debug,
-    H: Handler<Input = Request, Output = Result<Response, E>> + Sync
-{
-    pub fn new(inner: H, n_retries: usize) -> Self {
-        assert_ne!(n_retries, 0, "n_retries in Retries<H> must be greater than 0");
+// ######## BASE_HANDLER ########
+#[derive(Debug, Clone, Default)]
+pub struct NoRateLimiterBaseHandler;
 
-        // ? safety: new_unchecked is fine because it's checked above ^
-        Self { inner, n_retries: unsafe { NonZeroUsize::new_unchecked(n_retries) } }
+impl Handler for NoRateLimiterBaseHandler {
+    type Output = Result<reqwest::Response, NetError>;
+    type Input = Request;
+
+    async fn execute(
+        &self,
+        req: Self::Input,
+    ) -> Self::Output {
+        req.client.clone().execute_request_no_rate_limiter(req).await
     }
 }
 
-impl<E, H> Handler for Retries<H> 
-where 
-    E: Debug,
-    H: Handler<Input = Request, Output = Result<Response, E>> + Sync,
-{
+#[derive(Debug, Clone, Default)]
+pub struct BaseHandler;
+
+impl Handler for BaseHandler {
+    type Output = Result<reqwest::Response, NetError>;
     type Input = Request;
-    type Output = Result<reqwest::Response, RetriesError<E>>;
-
-    async fn execute(&self, req: Self::Input) -> Self::Output {
-        // let req = Arc::new(req);
-        let n_retries: usize = self.n_retries.into();
-        for n in 0..n_retries{
-            let Some(req) = req.try_clone() else {
-                return Err(RetriesError::CouldNotCloneRequest)
-            };
-
-            let x = self.inner.execute(req).await;
-
-            match x {
-                Ok(resp) => return Ok(resp),
-                Err(e) if n == n_retries => {
-                    return Err(RetriesError::InnerError(e));
-                },
-
-                _ => continue,
-            }
-        }
 
-        unreachable!()
+    async fn execute(
+        &self,
+        req: Self::Input,
+    ) -> Self::Output {
+        req.client.clone().execute_request(req).await
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,284 @@
+use std::{
+    fmt,
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
+
+use http::StatusCode;
+use reqwest::Response;
+
+use crate::{
+    handlers::Handler,
+    net::{Request, net_error::NetError, rate_limiter::parse_retry_after},
+    utils::random::random_fraction,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Jitter {
+    None,
+    // anywhere between zero and the full delay
+    #[default]
+    Full,
+    // at least half of the delay, plus up to the other half
+    Equal,
+}
+
+// decides whether an attempt gets another go and how long to wait before it. delays grow as
+// base_delay * 2^(attempt - 1) up to max_delay, then get jittered; a Retry-After on the response
+// replaces the computed delay, and one longer than max_delay ends the retries. E is the error
+// type of the handler being retried
+pub struct RetryPolicy<E = NetError> {
+    max_attempts: NonZeroUsize,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: Jitter,
+    max_elapsed: Option<Duration>,
+    respect_retry_after: bool,
+    statuses: Vec<StatusCode>,
+    retry_error: fn(&E) -> bool,
+}
+
+impl<E> RetryPolicy<E> {
+    // 3 attempts, 100ms doubling up to 10s with full jitter, Retry-After respected, and retries on
+    // 408, 429, 500, 502, 503 and 504. errors are never retried until `retry_on` says otherwise
+    pub fn new() -> Self {
+        Self {
+            max_attempts: NonZeroUsize::new(3).unwrap(),
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            jitter: Jitter::Full,
+            max_elapsed: None,
+            respect_retry_after: true,
+            statuses: vec![
+                StatusCode::REQUEST_TIMEOUT,
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            retry_error: |_| false,
+        }
+    }
+
+    // counts the first attempt too, so 1 means never retry
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = NonZeroUsize::new(max_attempts)
+            .expect("max_attempts in RetryPolicy must be greater than 0");
+        self
+    }
+
+    pub fn backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    // gives up instead of sleeping past this, counted from the start of the first attempt
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = Some(max_elapsed);
+        self
+    }
+
+    pub fn respect_retry_after(mut self, respect_retry_after: bool) -> Self {
+        self.respect_retry_after = respect_retry_after;
+        self
+    }
+
+    // replaces the retryable statuses
+    pub fn statuses(mut self, statuses: impl IntoIterator<Item = StatusCode>) -> Self {
+        self.statuses = statuses.into_iter().collect();
+        self
+    }
+
+    pub fn retry_on(mut self, retry_error: fn(&E) -> bool) -> Self {
+        self.retry_error = retry_error;
+        self
+    }
+
+    pub fn retries_status(&self, status: StatusCode) -> bool {
+        self.statuses.contains(&status)
+    }
+
+    pub fn retries_error(&self, error: &E) -> bool {
+        (self.retry_error)(error)
+    }
+
+    // the delay before the attempt after `attempt` (1-based), before jitter
+    pub fn backoff_for(&self, attempt: usize) -> Duration {
+        let exponent = u32::try_from(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(exponent))
+            .min(self.max_delay)
+    }
+
+    // None when the server asked to wait longer than max_delay, which isn't worth waiting for
+    fn delay_for(&self, attempt: usize, retry_after: Option<Duration>) -> Option<Duration> {
+        if let Some(retry_after) = retry_after.filter(|_| self.respect_retry_after) {
+            return (retry_after <= self.max_delay).then_some(retry_after);
+        }
+
+        let delay = self.backoff_for(attempt);
+        Some(match self.jitter {
+            Jitter::None => delay,
+            Jitter::Full => delay.mul_f64(random_fraction()),
+            Jitter::Equal => delay / 2 + (delay / 2).mul_f64(random_fraction()),
+        })
+    }
+}
+
+impl RetryPolicy<NetError> {
+    // connection failures and timeouts, on top of the statuses
+    pub fn transient() -> Self {
+        Self::new().retry_on(|e| match e {
+            NetError::ReqwestError(e) => e.is_connect() || e.is_timeout(),
+            _ => false,
+        })
+    }
+}
+
+impl Default for RetryPolicy<NetError> {
+    fn default() -> Self {
+        Self::transient()
+    }
+}
+
+// manual impls so that E doesn't have to be Clone/Debug itself
+impl<E> Clone for RetryPolicy<E> {
+    fn clone(&self) -> Self {
+        Self { statuses: self.statuses.clone(), ..*self }
+    }
+}
+
+impl<E> fmt::Debug for RetryPolicy<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .field("max_elapsed", &self.max_elapsed)
+            .field("respect_retry_after", &self.respect_retry_after)
+            .field("statuses", &self.statuses)
+            .finish_non_exhaustive()
+    }
+}
+
+// what Retries ended up with. a retryable status that ran out of attempts is still an Ok(Response),
+// so that whatever comes next can deal with it like any other status
+#[derive(Debug)]
+pub struct Retried<E> {
+    pub attempts: usize,
+    pub elapsed: Duration,
+    pub result: Result<Response, E>,
+}
+
+impl<E> Retried<E> {
+    pub fn into_result(self) -> Result<Response, E> {
+        self.result
+    }
+
+    pub fn last_error(&self) -> Option<&E> {
+        self.result.as_ref().err()
+    }
+}
+
+// ######## RETRIES ########
+// runs H again according to a RetryPolicy. a Request is only ever sent once if its body can't be
+// cloned (a stream), or if it isn't retry safe: not idempotent and without an Idempotency-Key
+#[derive(Debug)]
+pub struct Retries<H, E = NetError> {
+    pub inner: H,
+    pub policy: RetryPolicy<E>,
+}
+
+impl<H, E> Retries<H, E> {
+    pub fn new(inner: H, policy: RetryPolicy<E>) -> Self {
+        Self { inner, policy }
+    }
+}
+
+impl<H: Clone, E> Clone for Retries<H, E> {
+    fn clone(&self) -> Self {
+        Self::new(self.inner.clone(), self.policy.clone())
+    }
+}
+
+impl<H: Default> Default for Retries<H, NetError> {
+    fn default() -> Self {
+        Self::new(H::default(), RetryPolicy::default())
+    }
+}
+
+impl<H, E> Handler for Retries<H, E>
+where
+    E: fmt::Debug + Send,
+    H: Handler<Input = Request, Output = Result<Response, E>> + Sync,
+{
+    type Input = Request;
+    type Output = Retried<E>;
+
+    async fn execute(&self, req: Self::Input) -> Self::Output {
+        let policy = &self.policy;
+        let max_attempts: usize = match req.is_retry_safe() {
+            true => policy.max_attempts.into(),
+            false => 1,
+        };
+        let started = Instant::now();
+
+        let mut req = req;
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+
+            let next = match attempts < max_attempts {
+                true => req.try_clone(),
+                false => None,
+            };
+
+            let result = self.inner.execute(req).await;
+
+            let retry_after = match &result {
+                Ok(resp) if policy.retries_status(resp.status()) => parse_retry_after(resp.headers()),
+                Err(e) if policy.retries_error(e) => None,
+                _ => return Retried { attempts, elapsed: started.elapsed(), result },
+            };
+
+            let Some(next) = next else {
+                return Retried { attempts, elapsed: started.elapsed(), result };
+            };
+
+            let Some(delay) = policy.delay_for(attempts, retry_after) else {
+                return Retried { attempts, elapsed: started.elapsed(), result };
+            };
+
+            if let Some(max_elapsed) = policy.max_elapsed
+                && started.elapsed() + delay > max_elapsed
+            {
+                return Retried { attempts, elapsed: started.elapsed(), result };
+            }
+
+            tokio::time::sleep(delay).await;
+            req = next;
+        }
+    }
+}
+
+// for stacks that only care about the Response, e.g. `Retries<BaseHandler>, ForgetAttempts, MatchStatus<..>`
+#[derive(Debug, Clone, Default)]
+pub struct ForgetAttempts;
+
+impl Handler for ForgetAttempts {
+    type Input = Retried<NetError>;
+    type Output = Result<Response, NetError>;
+
+    async fn execute(&self, input: Self::Input) -> Self::Output {
+        input.into_result()
+    }
+}
@@ -6,6 +6,7 @@ pub mod handlers;
 pub mod utils;
 pub mod resources;
 pub mod provided;
+pub mod api;
 
 
 // half impl of a proc macro i'll make sometime
@@ -1,7 +1,8 @@
 #[cfg(not(feature = "async-trait"))]
 use crate::capability::CapabilityOutput;
 use crate::{capability::CapError, capability::Capability, net::RequestBuilder, utils::resource_string::ResourceString};
-use std::{fmt::Debug, error::Error as StdError};
+use std::fmt::Debug;
+#[cfg(feature = "reqwest-multipart")]
 use crate::utils::error::Error;
 
 #[derive(Debug)]
@@ -93,7 +94,7 @@ where
     F: Fn() -> Result<reqwest::multipart::Form, Error> + Send + Sync + 'static,
 {
     fn apply<'a>(&'a self, request: RequestBuilder) -> CapabilityOutput<'a> {
-        CapabilityOutput::new(async move { Ok(request.multipart((self.0)().map_err(|e| Box::new(e) as Box<dyn StdError + Send>)?)) })
+        CapabilityOutput::new(async move { Ok(request.multipart((self.0)().map_err(|e| Box::new(e) as CapError)?)) })
     }
 }
 
@@ -104,6 +105,6 @@ where
     F: Fn() -> Result<reqwest::multipart::Form, Error> + Send + Sync + 'static,
 {
     async fn apply(&self, request: RequestBuilder) -> Result<RequestBuilder, CapError> {
-        Ok(request.multipart((self.0)().map_err(|e| Box::new(e) as Box<dyn StdError + Send>)?))
+        Ok(request.multipart((self.0)().map_err(|e| Box::new(e) as CapError)?))
     }
 }
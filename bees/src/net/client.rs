@@ -1,45 +1,117 @@
 use crate::{
-    endpoint::{EndpointExt, EndpointInfo, HandlerStack, HandlerStackError},
-    handlers::{BaseHandler, Handler},
-    net::{bodies::Body, net_error::NetError, rate_limiter::RateLimiter},
+    capability::Capability,
+    endpoint::{EndpointExt, EndpointInfo, HandlerStack, ParsedPath},
+    handlers::Handler,
+    record::{Record, RecordInfo},
+    net::{
+        bodies::Body,
+        concurrency::{self, ConcurrencyLimiter, ConcurrencyPermit},
+        hosts::{self, HostPool},
+        net_error::NetError,
+        rate_limiter::RateLimiter,
+        transport::Transport,
+    },
     resources::resource_handler::ResourceManager,
+    utils::resource_string::ResourceString,
 };
-use futures::future::join;
 use reqwest::{Client as ReqClient, Method, Response};
-use std::{error::Error as StdError, fmt::Debug, sync::Arc};
+use dashmap::DashMap;
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    error::Error as StdError,
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use super::request::{Request, RequestBuilder};
+use super::{
+    client_builder::ClientBuilder,
+    request::{Request, RequestBuilder, RequestMeta},
+};
+#[cfg(feature = "cassette")]
+use crate::provided::transports::cassette::{Cassette, CassetteError};
 // use super::net_error::NetError as Error;
 use crate::utils::error::Error;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Client {
-    inner: Arc<ReqClient>,
-    rate_limiter: Arc<RateLimiter>,
+    pub(super) inner: Arc<ReqClient>,
+    pub(super) transport: Arc<dyn Transport>,
+    pub(super) rate_limiter: Arc<RateLimiter>,
+    pub(super) concurrency_limiter: Option<Arc<ConcurrencyLimiter>>,
+    // applied to every Endpoint's requests, before the Record's capabilities; Arcs so that
+    // derived Clients can share them
+    pub(super) capabilities: Arc<[Arc<dyn Capability>]>,
+    // default for every request this Client builds
+    pub(super) timeout: Option<Duration>,
     pub resource_manager: Arc<ResourceManager>,
+    // Endpoints' parsed paths, keyed by Endpoint type; tied to `resource_manager`, so shared
+    // only by Clients that share it
+    pub(super) path_cache: Arc<PathCache>,
+    // base urls that replace Records' SHARED_URL and mirrors, keyed by Record type. like SHARED_URL,
+    // they may contain <resource> placeholders, e.g. to read the base url out of a Resource
+    pub(super) base_urls: Arc<HashMap<TypeId, Vec<String>>>,
+    // for Records with more than one host; tied to `base_urls` like `path_cache` is
+    pub(super) host_pools: Arc<DashMap<TypeId, Arc<HostPool>>>,
+    // Records' and Endpoints' own limiters, made the first time they're needed so that every
+    // Client gets its own limits. shared by the Clients that share `rate_limiter`
+    pub(super) route_limiters: Arc<RouteLimiters>,
 }
 
-// impl fmt::Debug for Client {
-//     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-//         f.debug_struct("Client")
-//             .field("inner", &self.inner)
-//             .field("rate_limiter", &"async_rate_limiter internals")
-//             .finish()
-//     }
-// }
+pub(crate) type PathCache = DashMap<TypeId, Arc<ParsedPath>>;
+
+#[derive(Debug, Default)]
+pub(crate) struct RouteLimiters {
+    pub(crate) records: DashMap<TypeId, Option<Arc<RateLimiter>>>,
+    pub(crate) endpoints: DashMap<TypeId, Option<Arc<RateLimiter>>>,
+    pub(crate) concurrency: DashMap<TypeId, Option<Arc<ConcurrencyLimiter>>>,
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("inner", &self.inner)
+            .field("transport", &self.transport)
+            .field("rate_limiter", &self.rate_limiter)
+            .field("concurrency_limiter", &self.concurrency_limiter)
+            .field("capabilities", &self.capabilities.len())
+            .field("timeout", &self.timeout)
+            .field("base_urls", &self.base_urls.values().collect::<Vec<_>>())
+            .field("resource_manager", &self.resource_manager)
+            .finish()
+    }
+}
 
 impl Client {
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
     pub fn new(reqwest_client: ReqClient, rate_limiter: RateLimiter) -> Self {
         Self::__new(
-            reqwest_client,
+            reqwest_client.clone(),
+            Arc::new(reqwest_client),
             Arc::new(rate_limiter),
             ResourceManager::new(),
         )
     }
 
-    pub(crate) fn _new(rate_limiter: Arc<RateLimiter>) -> Self {
+    // the reqwest client is then only used to build requests, every request goes through `transport`
+    pub fn new_with_transport<T: Transport + 'static>(transport: T, rate_limiter: RateLimiter) -> Self {
         Self::__new(
             ReqClient::new(),
+            Arc::new(transport),
+            Arc::new(rate_limiter),
+            ResourceManager::new(),
+        )
+    }
+
+    pub(crate) fn _new(rate_limiter: Arc<RateLimiter>) -> Self {
+        let client = ReqClient::new();
+        Self::__new(
+            client.clone(),
+            Arc::new(client),
             rate_limiter,
             ResourceManager::new(),
         )
@@ -47,14 +119,155 @@ impl Client {
 
     pub(crate) fn __new(
         client: ReqClient,
+        transport: Arc<dyn Transport>,
         rate_limiter: Arc<RateLimiter>,
         res_manager: ResourceManager,
     ) -> Self {
         Self {
             inner: Arc::new(client),
+            transport,
             rate_limiter,
+            concurrency_limiter: None,
+            capabilities: Arc::new([]),
+            timeout: None,
             resource_manager: Arc::new(res_manager),
+            path_cache: Arc::default(),
+            base_urls: Arc::default(),
+            host_pools: Arc::default(),
+            route_limiters: Arc::default(),
+        }
+    }
+
+    // caps how many requests made through this Client (and its clones) can be in flight at once
+    pub fn with_concurrency_limit(&self, max_in_flight: usize) -> Self {
+        Self {
+            concurrency_limiter: Some(Arc::new(ConcurrencyLimiter::new(max_in_flight))),
+            ..self.clone()
+        }
+    }
+
+    // same everything, plus one more client-wide capability (applied after the existing ones).
+    // lets several Clients share Records while e.g. sending different user agents or tenant headers
+    pub fn with_capability<C: Capability + 'static>(&self, capability: C) -> Self {
+        let capabilities = self
+            .capabilities
+            .iter()
+            .cloned()
+            .chain([Arc::new(capability) as Arc<dyn Capability>])
+            .collect();
+
+        Self {
+            capabilities,
+            ..self.clone()
+        }
+    }
+
+    // replaces all of the client-wide capabilities
+    pub fn with_capabilities(&self, capabilities: impl IntoIterator<Item = Arc<dyn Capability>>) -> Self {
+        Self {
+            capabilities: capabilities.into_iter().collect(),
+            ..self.clone()
+        }
+    }
+
+    pub fn without_capabilities(&self) -> Self {
+        self.with_capabilities([])
+    }
+
+    pub fn capabilities(&self) -> Arc<[Arc<dyn Capability>]> {
+        self.capabilities.clone()
+    }
+
+    // same everything, but every Endpoint of `R` goes to `base_url` instead of `R::SHARED_URL`,
+    // e.g. to point the same binary at staging or at a local mock
+    pub fn with_base_url<R: Record + 'static>(&self, base_url: impl Into<String>) -> Self {
+        self.with_base_urls::<R>([base_url.into()])
+    }
+
+    // same as with_base_url, but with mirrors to fail over to (replacing the Record's own)
+    pub fn with_base_urls<R: Record + 'static>(&self, base_urls: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let base_urls = base_urls.into_iter().map(Into::into).collect::<Vec<_>>();
+        assert!(!base_urls.is_empty(), "a Record needs at least one base url");
+
+        let mut all = (*self.base_urls).clone();
+        all.insert(TypeId::of::<R>(), base_urls);
+
+        Self {
+            base_urls: Arc::new(all),
+            // the cached paths and hosts were built from the old base urls
+            path_cache: Arc::default(),
+            host_pools: Arc::default(),
+            ..self.clone()
+        }
+    }
+
+    // the one requests are built for, before any failover
+    pub fn base_url<R: Record + 'static>(&self) -> String {
+        self.base_urls::<R>().swap_remove(0)
+    }
+
+    pub fn base_urls<R: Record + 'static>(&self) -> Vec<String> {
+        self.base_urls_of(RecordInfo::of::<R>()).0
+    }
+
+    // along with the Record whose hosts they are: the first one up the chain that has an
+    // override or no parent
+    fn base_urls_of(&self, record: RecordInfo) -> (Vec<String>, RecordInfo) {
+        // root first, and cycles were already caught by chain
+        let chain = record.chain();
+        let owner = chain
+            .iter()
+            .rposition(|record| self.base_urls.contains_key(&(record.type_id)()))
+            .unwrap_or(0);
+
+        let mut base_urls = match self.base_urls.get(&(chain[owner].type_id)()) {
+            Some(base_urls) => base_urls.clone(),
+            None => {
+                let mirrors = (chain[owner].mirrors)().iter().map(|mirror| mirror.to_string());
+                [chain[owner].shared_url.to_string()].into_iter().chain(mirrors).collect()
+            }
+        };
+
+        for record in &chain[owner + 1..] {
+            for base in &mut base_urls {
+                *base = format!("{}/{}", base.trim_end_matches('/'), record.shared_url.trim_start_matches('/'));
+            }
         }
+
+        (base_urls, chain[owner])
+    }
+
+    pub fn host_pool<R: Record + 'static>(&self) -> Option<Arc<HostPool>> {
+        let (base_urls, owner) = self.base_urls_of(RecordInfo::of::<R>());
+        if base_urls.len() < 2 {
+            return None;
+        }
+
+        let pool = self.host_pools.entry(TypeId::of::<R>()).or_insert_with(|| {
+            let bases = base_urls.iter().map(|base| ResourceString::new(self, base)).collect();
+            Arc::new(HostPool::new(bases, (owner.host_strategy)()))
+        });
+
+        Some(pool.clone())
+    }
+
+    // same rate limiter and resources, different transport
+    pub fn with_transport<T: Transport + 'static>(&self, transport: T) -> Self {
+        Self {
+            transport: Arc::new(transport),
+            ..self.clone()
+        }
+    }
+
+    // --------- CASSETTES ---------
+    #[cfg(feature = "cassette")]
+    pub fn recording(&self, path: impl AsRef<std::path::Path>) -> Self {
+        self.with_transport(Cassette::record(path, self.transport.clone()))
+    }
+
+    #[cfg(feature = "cassette")]
+    pub fn replaying(&self, path: impl AsRef<std::path::Path>) -> Result<Self, CassetteError> {
+        Ok(self.with_transport(Cassette::replay(path)?))
     }
 
     // --------- DIRECT ---------
@@ -69,16 +282,10 @@ impl Client {
         f(self.inner.clone())?.await
     }
 
-    pub async fn execute_request_no_rate_limiter(
-        &self,
-        request: Request,
-    ) -> Result<Response, Error> {
-        // self.rate_limiter.acquire().await;
-        Ok(self
-            .inner
-            .execute(request.inner)
-            .await
-            .map_err(NetError::ReqwestError)?)
+    // everything execute_request does (the Record's concurrency limit, failover to mirrors),
+    // except waiting on the rate limiters
+    pub async fn execute_request_no_rate_limiter(&self, request: Request) -> Result<Response, NetError> {
+        self.execute(request, false).await
     }
 
     //////// RATE LIMITER ////////
@@ -97,15 +304,24 @@ impl Client {
         method: Method,
         url: impl reqwest::IntoUrl,
     ) -> RequestBuilder {
+        let idempotent = method.is_idempotent();
+        let mut inner = self.inner.request(method, url);
+        if let Some(timeout) = self.timeout {
+            inner = inner.timeout(timeout);
+        }
+
         RequestBuilder {
-            inner: self.inner.request(method, url),
+            inner,
             client: self.clone(),
+            meta: RequestMeta { idempotent, ..RequestMeta::default() },
         }
     }
 
     pub async fn execute_reqwest_req(&self, request: reqwest::Request) -> Result<Response, NetError> {
+        let permits = self.acquire_permits(None).await;
         self.rate_limiter.acquire().await;
-        self.execute_reqwest_req_no_rate_limit(request).await
+        let response = self.send(request, &self.rate_limiter).await?;
+        Ok(concurrency::attach_permits(response, permits))
     }
 
     pub async fn execute_reqwest_req_no_rate_limit(
@@ -113,16 +329,101 @@ impl Client {
         request: reqwest::Request,
     ) -> Result<Response, NetError> {
         // self.rate_limiter.acquire().await;
-        Ok(self.inner.execute(request).await?)
+        let permits = self.acquire_permits(None).await;
+        let response = self.send(request, &self.rate_limiter).await?;
+        Ok(concurrency::attach_permits(response, permits))
     }
 
     pub async fn execute_request(&self, request: Request) -> Result<Response, NetError> {
-        self.rate_limiter.acquire().await;
-        self
-            .inner
-            .execute(request.inner)
-            .await
-            .map_err(NetError::ReqwestError)
+        self.execute(request, true).await
+    }
+
+    async fn execute(&self, request: Request, rate_limited: bool) -> Result<Response, NetError> {
+        // permits come first, so a request that's waiting on a free slot isn't also
+        // holding on to a rate limit token it can't use yet
+        let permits = self.acquire_permits(request.meta.concurrency_limiter.as_ref()).await;
+
+        // rate limit headers describe the quota of the route that was called, so they go to
+        // the most specific limiter this request has
+        let observer = request.meta.rate_limiters.last().unwrap_or(&self.rate_limiter);
+
+        let Some(route) = &request.meta.hosts else {
+            if rate_limited {
+                self.rate_limit(&request.meta).await;
+            }
+            let response = self.send(request.inner, observer).await?;
+            return Ok(concurrency::attach_permits(response, permits));
+        };
+
+        // every attempt is a request of its own as far as the rate limiters are concerned
+        let retry_safe = request.is_retry_safe();
+        let order = route.pool.order();
+        let mut inner = request.inner;
+
+        for (attempt, &host) in order.iter().enumerate() {
+            // a request whose body can't be cloned only gets one try
+            let retry = match attempt + 1 < order.len() {
+                true => inner.try_clone(),
+                false => None,
+            };
+
+            *inner.url_mut() = route.url_for(host, inner.url())?;
+
+            if rate_limited {
+                self.rate_limit(&request.meta).await;
+            }
+            let started = Instant::now();
+            let result = self.send(inner, observer).await;
+
+            let failed = hosts::host_failed(&result);
+            route.pool.report(host, !failed, started.elapsed());
+
+            // a request that isn't retry safe only moves on if the host never got it, so that
+            // a POST the server did see isn't sent to its mirror too
+            let fail_over = failed && (retry_safe || hosts::never_sent(&result));
+
+            match retry {
+                Some(next) if fail_over => inner = next,
+                _ => return result.map(|response| concurrency::attach_permits(response, permits)),
+            }
+        }
+
+        unreachable!("a host pool always has at least two hosts")
+    }
+
+    // most specific first, client-wide last: when a limiter makes us wait, the ones acquired
+    // before it hand out their slot too early, and the client-wide one is the one that
+    // must never be overrun
+    async fn rate_limit(&self, meta: &RequestMeta) {
+        for rate_limiter in meta.rate_limiters.iter().rev() {
+            rate_limiter.acquire_with_priority(meta.cost, meta.priority).await;
+        }
+        self.rate_limiter.acquire_with_priority(meta.cost, meta.priority).await;
+    }
+
+    // the Record's limiter before the Client's, for the same reason as with the rate limiters
+    async fn acquire_permits(&self, record_limiter: Option<&Arc<ConcurrencyLimiter>>) -> Vec<ConcurrencyPermit> {
+        let mut permits = Vec::new();
+        for limiter in record_limiter.into_iter().chain(self.concurrency_limiter.as_ref()) {
+            permits.push(limiter.acquire().await);
+        }
+        permits
+    }
+
+    // every response that goes through the transport is shown to a rate limiter, even on
+    // the no-rate-limit paths, so that it can back off when the server asks.
+    // callers hand their permits to the response, so they're held until its body is read or dropped
+    async fn send(&self, request: reqwest::Request, observer: &RateLimiter) -> Result<Response, NetError> {
+        let response = self.transport.execute(request).await?;
+        observer.observe(&response);
+
+        // quota headers only describe the route, but a 429/503 with Retry-After is the server
+        // asking everyone to back off, so the client-wide limiter pauses too
+        if !std::ptr::eq(observer, &*self.rate_limiter) {
+            self.rate_limiter.observe_pause(response.status(), response.headers());
+        }
+
+        Ok(response)
     }
 
     // --------- ENDPOINT ---------
@@ -133,11 +434,21 @@ impl Client {
         // determine whether this makes sense, does it give enough of a speed boost to
         // justify not guaranteeing order of operations?
 
-        let url = E::full_url(&self.resource_manager, call_context).await?;
+        let url = E::full_url(self, call_context).await?;
+        let hosts = match self.host_pool::<E::Record>() {
+            Some(pool) => pool.route(&url).await?,
+            None => None,
+        };
         let method = E::http_method(call_context).await;
 
 
-        let request = self.get_raw_request_builder(method.verb.as_reqwest_method(), url);
+        let mut request = self.get_raw_request_builder(method.verb.as_reqwest_method(), url);
+        request.meta.rate_limiters = E::rate_limiters(self);
+        request.meta.cost = E::cost(call_context);
+        request.meta.priority = E::priority(call_context);
+        request.meta.hosts = hosts;
+        request.meta.concurrency_limiter = E::concurrency_limiter(self);
+        request.meta.idempotent = E::idempotent(call_context).unwrap_or(method.verb.is_idempotent());
 
         let mut request = match method.body {
             Some(body) => body.add_body(request).await?,
@@ -147,7 +458,13 @@ impl Client {
         let endpoint_caps = E::capabilities(call_context);
         let record_caps = E::record_capabilities();
 
-        let capabilities = record_caps.iter().chain(endpoint_caps.iter());
+        // client-wide first, so that Records and Endpoints get the last word
+        let capabilities = self
+            .capabilities
+            .iter()
+            .map(AsRef::as_ref)
+            .chain(record_caps.iter().flat_map(|caps| caps.iter()).map(AsRef::as_ref))
+            .chain(endpoint_caps.iter().map(AsRef::as_ref));
 
         for capability in capabilities {
             request = capability.apply(request).await?
@@ -202,9 +519,9 @@ impl Client {
         Ok(handlers.execute(self.get_request::<E>(&mut call_context).await?).await)
     }
 
-    pub async fn run_endpoint_ref_with<'a, E: EndpointInfo + HandlerStack<O>, O>(
+    pub async fn run_endpoint_ref_with<E: EndpointInfo + HandlerStack<O>, O>(
         &self,
-        call_context: &'a mut E::CallContext,
+        call_context: &mut E::CallContext,
     ) -> Result<O, Error> {
         let handlers = E::handlers(call_context).await?;
 
@@ -217,7 +534,7 @@ impl Client {
         self.run_endpoint_with::<E, O>(()).await
     }
 
-    pub async fn run_endpoint_ref<'a, E: EndpointInfo<CallContext = ()> + HandlerStack<O>, O>(
+    pub async fn run_endpoint_ref<E: EndpointInfo<CallContext = ()> + HandlerStack<O>, O>(
         &self,
     ) -> Result<O, Error>  {
         self.run_endpoint_ref_with::<E, O>(&mut ()).await
@@ -226,6 +543,22 @@ impl Client {
     pub fn get_rate_limiter(&self) -> Arc<RateLimiter> {
         self.rate_limiter.clone()
     }
+
+    pub fn get_concurrency_limiter(&self) -> Option<Arc<ConcurrencyLimiter>> {
+        self.concurrency_limiter.clone()
+    }
+
+    pub(crate) fn path_cache(&self) -> &PathCache {
+        &self.path_cache
+    }
+
+    pub(crate) fn route_limiters(&self) -> &RouteLimiters {
+        &self.route_limiters
+    }
+
+    pub fn get_transport(&self) -> Arc<dyn Transport> {
+        self.transport.clone()
+    }
 }
 
 // #[derive(Debug)]
@@ -402,4 +735,9 @@ impl HttpVerb {
             HttpVerb::HEAD => Method::HEAD,
         }
     }
+
+    // RFC 9110's idempotent methods: everything but POST and PATCH
+    pub fn is_idempotent(&self) -> bool {
+        self.as_reqwest_method().is_idempotent()
+    }
 }
@@ -0,0 +1,201 @@
+use std::{any::TypeId, collections::HashMap, fmt, sync::Arc, time::Duration};
+
+use derive_more::{Display, Error, From};
+use reqwest::ClientBuilder as ReqClientBuilder;
+
+use crate::{
+    capability::Capability,
+    record::Record,
+    net::{Client, concurrency::ConcurrencyLimiter, rate_limiter::RateLimiter, transport::Transport},
+    resources::{dyn_resource::DynResource, resource::Resource, resource_handler::ResourceManager},
+};
+
+#[derive(Debug, Display, Error, From)]
+#[display("ClientBuildError: {_variant}")]
+pub enum ClientBuildError {
+    #[display("couldn't build the reqwest client: {_0}")]
+    Reqwest(#[error(source)] reqwest::Error),
+
+    #[from(skip)]
+    #[display("no rate limiter was given")]
+    MissingRateLimiter,
+
+    #[from(skip)]
+    #[display("max_in_flight may not be 0")]
+    ZeroMaxInFlight,
+
+    #[from(skip)]
+    #[display("the {_0} timeout may not be 0")]
+    ZeroTimeout(#[error(not(source))] &'static str),
+
+    #[from(skip)]
+    #[display("a Record was given an empty list of base urls")]
+    NoBaseUrls,
+
+    #[from(skip)]
+    #[display("two resources were given for `{_0}`")]
+    DuplicateResource(#[error(not(source))] String),
+}
+
+// everything is only checked once `build` is called
+pub struct ClientBuilder {
+    reqwest: ReqClientBuilder,
+    transport: Option<Arc<dyn Transport>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    max_in_flight: Option<usize>,
+    resources: Vec<DynResource>,
+    capabilities: Vec<Arc<dyn Capability>>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    base_urls: HashMap<TypeId, Vec<String>>,
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        Self {
+            reqwest: ReqClientBuilder::new(),
+            transport: None,
+            rate_limiter: None,
+            max_in_flight: None,
+            resources: Vec::new(),
+            capabilities: Vec::new(),
+            timeout: None,
+            connect_timeout: None,
+            base_urls: HashMap::new(),
+        }
+    }
+
+    // for any reqwest option that doesn't have its own method here (proxies, tls, redirects, ...)
+    pub fn configure_reqwest(mut self, f: impl FnOnce(ReqClientBuilder) -> ReqClientBuilder) -> Self {
+        self.reqwest = f(self.reqwest);
+        self
+    }
+
+    // the reqwest client is then only used to build requests, see Client::new_with_transport
+    pub fn transport<T: Transport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    pub fn rate_limiter(self, rate_limiter: RateLimiter) -> Self {
+        self.shared_rate_limiter(Arc::new(rate_limiter))
+    }
+
+    // for a limiter that's shared with other Clients
+    pub fn shared_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = Some(max_in_flight);
+        self
+    }
+
+    pub fn resource<R: Resource + 'static>(mut self, resource: R) -> Self {
+        self.resources.push(DynResource::from_res(resource));
+        self
+    }
+
+    pub fn capability<C: Capability + 'static>(mut self, capability: C) -> Self {
+        self.capabilities.push(Arc::new(capability));
+        self
+    }
+
+    // set on every request the Client builds, so that it holds no matter the transport;
+    // RequestBuilder::timeout still overrides it for a single request
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    // see Client::with_base_url
+    pub fn base_url<R: Record + 'static>(self, base_url: impl Into<String>) -> Self {
+        self.base_urls::<R>([base_url.into()])
+    }
+
+    // see Client::with_base_urls
+    pub fn base_urls<R: Record + 'static>(mut self, base_urls: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let base_urls = base_urls.into_iter().map(Into::into).collect();
+        self.base_urls.insert(TypeId::of::<R>(), base_urls);
+        self
+    }
+
+    pub fn build(self) -> Result<Client, ClientBuildError> {
+        let rate_limiter = self.rate_limiter.ok_or(ClientBuildError::MissingRateLimiter)?;
+
+        let concurrency_limiter = match self.max_in_flight {
+            Some(0) => return Err(ClientBuildError::ZeroMaxInFlight),
+            Some(max) => Some(Arc::new(ConcurrencyLimiter::new(max))),
+            None => None,
+        };
+
+        if self.timeout.is_some_and(|t| t.is_zero()) {
+            return Err(ClientBuildError::ZeroTimeout("request"));
+        }
+
+        let mut reqwest = self.reqwest;
+        if let Some(connect_timeout) = self.connect_timeout {
+            if connect_timeout.is_zero() {
+                return Err(ClientBuildError::ZeroTimeout("connect"));
+            }
+            reqwest = reqwest.connect_timeout(connect_timeout);
+        }
+
+        if self.base_urls.values().any(Vec::is_empty) {
+            return Err(ClientBuildError::NoBaseUrls);
+        }
+
+        let resource_manager = ResourceManager::new();
+        for resource in self.resources {
+            let ident = resource.inner.ident().to_string();
+            if !resource_manager.insert(resource) {
+                return Err(ClientBuildError::DuplicateResource(ident));
+            }
+        }
+
+        let reqwest = reqwest.build()?;
+        let transport = self.transport.unwrap_or_else(|| Arc::new(reqwest.clone()));
+
+        Ok(Client {
+            inner: Arc::new(reqwest),
+            transport,
+            rate_limiter,
+            concurrency_limiter,
+            capabilities: self.capabilities.into(),
+            timeout: self.timeout,
+            resource_manager: Arc::new(resource_manager),
+            path_cache: Arc::default(),
+            base_urls: Arc::new(self.base_urls),
+            host_pools: Arc::default(),
+            route_limiters: Arc::default(),
+        })
+    }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientBuilder")
+            .field("reqwest", &self.reqwest)
+            .field("transport", &self.transport)
+            .field("rate_limiter", &self.rate_limiter)
+            .field("max_in_flight", &self.max_in_flight)
+            .field("resources", &self.resources)
+            .field("capabilities", &self.capabilities.len())
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("base_urls", &self.base_urls.values().collect::<Vec<_>>())
+            .finish()
+    }
+}
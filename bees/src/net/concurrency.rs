@@ -0,0 +1,108 @@
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use reqwest::Response;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::net::transport::response_from_parts;
+
+// bounds how many requests can be in flight at once. a request counts as in flight from right
+// before it's rate limited until its response body has been read to the end or dropped
+#[derive(Debug)]
+pub struct ConcurrencyLimiter {
+    semaphore: Arc<Semaphore>,
+    max_in_flight: usize,
+}
+
+#[derive(Debug)]
+pub struct ConcurrencyPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(max_in_flight: usize) -> Self {
+        assert!(max_in_flight > 0, "max_in_flight may not be 0");
+
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_in_flight)),
+            max_in_flight,
+        }
+    }
+
+    pub async fn acquire(&self) -> ConcurrencyPermit {
+        let permit = self
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            // ? the semaphore is never closed
+            .expect("ConcurrencyLimiter's semaphore got closed");
+
+        ConcurrencyPermit { _permit: permit }
+    }
+
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.max_in_flight - self.semaphore.available_permits()
+    }
+}
+
+// hands the permits over to the response's body, so they're given back once the body is done
+pub(crate) fn attach_permits(response: Response, permits: Vec<ConcurrencyPermit>) -> Response {
+    if permits.is_empty() {
+        return response;
+    }
+
+    let url = response.url().clone();
+    let (parts, body) = http::Response::<reqwest::Body>::from(response).into_parts();
+
+    if body.is_end_stream() {
+        // nothing will ever poll an empty body, so there's nothing to hold on to
+        return response_from_parts(parts, body, url);
+    }
+
+    let body = reqwest::Body::wrap(PermitBody {
+        inner: body,
+        permits,
+    });
+
+    response_from_parts(parts, body, url)
+}
+
+struct PermitBody {
+    inner: reqwest::Body,
+    permits: Vec<ConcurrencyPermit>,
+}
+
+impl HttpBody for PermitBody {
+    type Data = <reqwest::Body as HttpBody>::Data;
+    type Error = <reqwest::Body as HttpBody>::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let polled = Pin::new(&mut self.inner).poll_frame(cx);
+
+        if let Poll::Ready(None | Some(Err(_))) = polled {
+            self.permits.clear();
+        }
+
+        polled
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use reqwest::Url;
+
+use crate::{
+    net::net_error::NetError,
+    utils::resource_string::{FormatStringError, ResourceString},
+};
+
+// how long a host is avoided after it failed a request
+const UNHEALTHY_FOR: Duration = Duration::from_secs(30);
+
+// how a Record with mirrors picks the host for each request. unhealthy hosts are always
+// tried last, and only if every healthy one failed too
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HostStrategy {
+    // the first listed host, then the next one, and so on (primary/secondary)
+    #[default]
+    Failover,
+    // a different host for each request
+    RoundRobin,
+    // the host that's been answering the fastest; hosts that haven't answered yet go first
+    LowestLatency,
+}
+
+#[derive(Debug)]
+struct Host {
+    base: ResourceString,
+    unhealthy_until: Mutex<Option<Instant>>,
+    // exponentially weighted moving average, 0 until the host has answered once
+    latency_nanos: AtomicU64,
+}
+
+// health and latency of a Record's hosts, for one Client
+#[derive(Debug)]
+pub struct HostPool {
+    hosts: Vec<Host>,
+    strategy: HostStrategy,
+    next: AtomicUsize,
+}
+
+impl HostPool {
+    pub(crate) fn new(bases: Vec<ResourceString>, strategy: HostStrategy) -> Self {
+        let hosts = bases
+            .into_iter()
+            .map(|base| Host {
+                base,
+                unhealthy_until: Mutex::new(None),
+                latency_nanos: AtomicU64::new(0),
+            })
+            .collect();
+
+        Self {
+            hosts,
+            strategy,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.hosts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hosts.is_empty()
+    }
+
+    pub fn strategy(&self) -> HostStrategy {
+        self.strategy
+    }
+
+    pub fn is_healthy(&self, host: usize) -> bool {
+        let unhealthy_until = *self.hosts[host].unhealthy_until.lock().unwrap();
+        unhealthy_until.is_none_or(|until| until <= Instant::now())
+    }
+
+    // the hosts to try a request on, in order
+    pub fn order(&self) -> Vec<usize> {
+        let mut order = (0..self.hosts.len()).collect::<Vec<_>>();
+
+        match self.strategy {
+            HostStrategy::Failover => {}
+            HostStrategy::RoundRobin => {
+                let start = self.next.fetch_add(1, Ordering::Relaxed) % order.len();
+                order.rotate_left(start);
+            }
+            HostStrategy::LowestLatency => {
+                order.sort_by_key(|&i| self.hosts[i].latency_nanos.load(Ordering::Relaxed));
+            }
+        }
+
+        // stable, so the strategy's order holds within healthy and within unhealthy hosts
+        order.sort_by_key(|&i| !self.is_healthy(i));
+        order
+    }
+
+    pub fn report(&self, host: usize, healthy: bool, latency: Duration) {
+        let host = &self.hosts[host];
+
+        if !healthy {
+            *host.unhealthy_until.lock().unwrap() = Some(Instant::now() + UNHEALTHY_FOR);
+            return;
+        }
+
+        *host.unhealthy_until.lock().unwrap() = None;
+
+        let latency = latency.as_nanos() as u64;
+        // a lost race here only loses one sample
+        let average = match host.latency_nanos.load(Ordering::Relaxed) {
+            0 => latency,
+            average => (average * 4 + latency) / 5,
+        };
+        host.latency_nanos.store(average.max(1), Ordering::Relaxed);
+    }
+
+    pub(crate) async fn route(self: &Arc<Self>, url: &Url) -> Result<Option<HostRoute>, FormatStringError> {
+        let mut bases = Vec::with_capacity(self.hosts.len());
+        for host in &self.hosts {
+            bases.push(host.base.to_formatted_now().await?.trim_end_matches('/').to_string());
+        }
+
+        // urls that modify_url moved away from the first host can't be moved to another one
+        if !url.as_str().starts_with(&bases[0]) {
+            return Ok(None);
+        }
+
+        Ok(Some(HostRoute {
+            pool: self.clone(),
+            bases: bases.into(),
+        }))
+    }
+}
+
+// lets a request built for the first host of a pool be sent to any of the others
+#[derive(Debug, Clone)]
+pub struct HostRoute {
+    pub pool: Arc<HostPool>,
+    bases: Arc<[String]>,
+}
+
+impl HostRoute {
+    pub fn url_for(&self, host: usize, url: &Url) -> Result<Url, NetError> {
+        let rest = url.as_str().strip_prefix(&*self.bases[0]).unwrap_or_default();
+        Ok(Url::parse(&format!("{}{rest}", self.bases[host]))?)
+    }
+}
+
+// whether a failure is the host's fault, so that another host might do better
+pub(crate) fn host_failed(result: &Result<reqwest::Response, NetError>) -> bool {
+    match result {
+        Ok(response) => response.status().is_server_error(),
+        Err(NetError::ReqwestError(e)) => e.is_connect() || e.is_timeout(),
+        #[allow(unreachable_patterns)]
+        Err(_) => false,
+    }
+}
+
+// whether the request never made it to the host, so that sending it elsewhere can't repeat it
+pub(crate) fn never_sent(result: &Result<reqwest::Response, NetError>) -> bool {
+    matches!(result, Err(NetError::ReqwestError(e)) if e.is_connect())
+}
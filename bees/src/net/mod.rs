@@ -1,11 +1,16 @@
 // use crate::context::context;
 pub mod client;
+pub mod client_builder;
 pub mod request;
 pub mod net_error;
 pub mod bodies;
 pub mod rate_limiter;
+pub mod transport;
+pub mod concurrency;
+pub mod hosts;
 
 pub use client::*;
+pub use client_builder::*;
 pub use request::*;
 
 // static RATE_LIMITER_DURATION: OnceLock<RateLimiter> = OnceLock::new();
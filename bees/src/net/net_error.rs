@@ -5,4 +5,7 @@ pub enum NetError {
     ReqwestError(#[error(source)] reqwest::Error),
 
     NotAValidUrl(#[error(source)] url::ParseError),
+
+    #[cfg(feature = "cassette")]
+    CassetteError(#[error(source)] crate::provided::transports::cassette::CassetteError),
 }
\ No newline at end of file
@@ -1,43 +1,754 @@
-use std::{sync::atomic::{AtomicU64, Ordering}};
-use tokio::time::{Instant as TokioInstant, Duration as TokioDuration};
+use std::{
+    cmp::Reverse,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}},
+    time::{Duration, SystemTime},
+};
+use http::{HeaderMap, StatusCode, header::RETRY_AFTER};
+use reqwest::Response;
+use tokio::{
+    sync::Notify,
+    time::{Instant as TokioInstant, Duration as TokioDuration},
+};
 
-#[derive(Debug)]
-pub struct RateLimiter {
-    next_at: AtomicU64,
-    start: TokioInstant,
-    nanos_per_token: u64,
-    burst_nanos: u64,
+// internal timestamps are nanos since `start` plus this, so that "now - burst" never saturates
+// to 0 and a fresh limiter really has its whole burst available
+const ORIGIN: u64 = 1 << 62;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Window {
+    // smooth spacing with some burst allowance (generic cell rate algorithm)
+    Rate {
+        nanos_per_token: u64,
+        burst_nanos: u64,
+        persistent: bool,
+    },
+    // at most `limit` tokens per `period`; periods are counted from the unix epoch, so a daily
+    // window resets at midnight UTC like most providers' daily quotas do
+    Fixed {
+        limit: u64,
+        period: Duration,
+        persistent: bool,
+    },
 }
 
-impl RateLimiter {
-    pub fn new(rate_per_sec: f64, burst: u64) -> Self {
+impl Window {
+    pub fn rate(rate_per_sec: f64, burst: u64) -> Self {
         assert_ne!(rate_per_sec, 0.0, "rate_per_sec may not be 0");
         assert!(rate_per_sec.is_finite(), "rate_per_sec may only be finite");
         assert!(burst > 0, "burst may not be 0");
 
         // ceil so you always stay juuust under to never get yourself yeeted from the api
         let nanos_per_token = (1_000_000_000.0 / rate_per_sec).ceil() as u64;
-        Self {
-            next_at: AtomicU64::new(0),
-            start: TokioInstant::now(),
+        Window::Rate {
             nanos_per_token,
             burst_nanos: nanos_per_token * burst,
+            persistent: false,
+        }
+    }
+
+    pub fn fixed(limit: u64, period: Duration) -> Self {
+        assert!(limit > 0, "limit may not be 0");
+        assert!(!period.is_zero(), "period may not be 0");
+
+        Window::Fixed { limit, period, persistent: false }
+    }
+
+    pub fn per_second(limit: u64) -> Self {
+        Self::fixed(limit, Duration::from_secs(1))
+    }
+
+    pub fn per_minute(limit: u64) -> Self {
+        Self::fixed(limit, Duration::from_secs(60))
+    }
+
+    pub fn per_hour(limit: u64) -> Self {
+        Self::fixed(limit, Duration::from_secs(60 * 60))
+    }
+
+    pub fn per_day(limit: u64) -> Self {
+        Self::fixed(limit, Duration::from_secs(24 * 60 * 60))
+    }
+
+    // saved by RateLimiter::save and loaded back by RateLimiter::persist_to
+    pub fn persistent(mut self) -> Self {
+        match &mut self {
+            Window::Rate { persistent, .. } | Window::Fixed { persistent, .. } => *persistent = true,
+        }
+        self
+    }
+
+    pub fn is_persistent(&self) -> bool {
+        match self {
+            Window::Rate { persistent, .. } | Window::Fixed { persistent, .. } => *persistent,
+        }
+    }
+}
+
+// which waiters get a slot first when several are waiting on the same limiter
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bottleneck {
+    // index into RateLimiter::windows()
+    Window(usize),
+    // the server asked us to stop (429, or no quota left)
+    Paused,
+    // the server said we're running low on quota
+    Throttled,
+    // other waiters were served first
+    Queued,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Acquired {
+    pub waited: Duration,
+    // whatever made this acquisition wait, if anything did
+    pub bottleneck: Option<Bottleneck>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum WindowState {
+    Rate { next_at: u64 },
+    Fixed { period_index: u64, count: u64 },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Throttle {
+    nanos_per_token: u64,
+    until: u64,
+    next_at: u64,
+}
+
+#[derive(Debug)]
+struct State {
+    windows: Vec<WindowState>,
+    // nobody gets a slot before this
+    paused_until: u64,
+    // while the server says we're low on quota, space requests out more than the windows would
+    throttle: Option<Throttle>,
+
+    // in arrival order
+    waiters: Vec<Waiter>,
+    next_waiter_id: u64,
+}
+
+impl State {
+    // the waiter whose turn it is: starved waiters first, then by priority, oldest first within either
+    fn next_up(&self, now: u64, starvation_limit: u64) -> Option<u64> {
+        self.waiters
+            .iter()
+            .max_by_key(|w| (now.saturating_sub(w.since) >= starvation_limit, w.priority, Reverse(w.id)))
+            .map(|w| w.id)
+    }
+}
+
+#[derive(Debug)]
+struct Waiter {
+    id: u64,
+    priority: Priority,
+    since: u64,
+    // poked when it might have become this waiter's turn
+    notify: Arc<Notify>,
+}
+
+// keeps a waiter in the queue for as long as its acquire is alive, even if it's cancelled
+struct WaiterGuard<'a> {
+    limiter: &'a RateLimiter,
+    id: u64,
+    since: u64,
+    notify: Arc<Notify>,
+}
+
+impl WaiterGuard<'_> {
+    fn ages_at(&self, starvation_limit: u64) -> Option<u64> {
+        self.since.checked_add(starvation_limit)
+    }
+}
+
+impl Drop for WaiterGuard<'_> {
+    fn drop(&mut self) {
+        let mut state = self.limiter.state.lock().unwrap();
+        state.waiters.retain(|w| w.id != self.id);
+
+        if let Some(next) = state.next_up(self.limiter.now(), self.limiter.starvation_limit) {
+            let waiter = state.waiters.iter().find(|w| w.id == next);
+            waiter.expect("next_up only returns queued waiters").notify.notify_one();
         }
     }
+}
+
+enum Wait {
+    // our turn, but the limiter isn't ready until then
+    Slot(u64),
+    // someone else's turn; we'll go ahead of them at this time if they're still waiting
+    Turn(Option<u64>),
+}
+
+#[derive(Debug)]
+struct Persistence {
+    path: PathBuf,
+    interval: Duration,
+    last_saved: Mutex<TokioInstant>,
+}
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    windows: Vec<Window>,
+    state: Mutex<State>,
+    start: TokioInstant,
+    start_unix_nanos: u128,
+
+    adaptive: AtomicBool,
+    default_retry_after: Duration,
+    starvation_limit: u64,
+
+    persistence: Option<Persistence>,
+}
+
+impl RateLimiter {
+    pub fn new(rate_per_sec: f64, burst: u64) -> Self {
+        Self::with_windows([Window::rate(rate_per_sec, burst)])
+    }
 
-    pub async fn acquire(&self) {
-        let now = self.start.elapsed().as_nanos() as u64;
-        let earliest = now.saturating_sub(self.burst_nanos);
+    // every window is enforced at once, e.g. `[Window::rate(10.0, 10), Window::per_day(10_000)]`
+    pub fn with_windows(windows: impl IntoIterator<Item = Window>) -> Self {
+        let windows = windows.into_iter().collect::<Vec<_>>();
+        assert!(!windows.is_empty(), "a RateLimiter needs at least one window");
 
-        let slot = self.next_at
-            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |next| {
-                Some(next.max(earliest) + self.nanos_per_token)
+        let state = windows
+            .iter()
+            .map(|window| match window {
+                Window::Rate { .. } => WindowState::Rate { next_at: 0 },
+                Window::Fixed { .. } => WindowState::Fixed { period_index: 0, count: 0 },
             })
-            .unwrap()
-            .max(earliest);
+            .collect();
+
+        Self {
+            windows,
+            state: Mutex::new(State {
+                windows: state,
+                paused_until: 0,
+                throttle: None,
+                waiters: Vec::new(),
+                next_waiter_id: 0,
+            }),
+            start: TokioInstant::now(),
+            start_unix_nanos: unix_nanos_now(),
+
+            adaptive: AtomicBool::new(true),
+            default_retry_after: Duration::from_secs(1),
+            starvation_limit: Duration::from_secs(10).as_nanos() as u64,
+
+            persistence: None,
+        }
+    }
+
+    // how long to back off after a 429 that doesn't say how long to back off for
+    pub fn with_default_retry_after(mut self, retry_after: Duration) -> Self {
+        self.default_retry_after = retry_after;
+        self
+    }
+
+    // how long a waiter may be passed over by more important ones before it goes first anyway
+    pub fn with_starvation_limit(mut self, limit: Duration) -> Self {
+        self.starvation_limit = limit.as_nanos() as u64;
+        self
+    }
+
+    pub fn windows(&self) -> &[Window] {
+        &self.windows
+    }
+
+    pub fn set_adaptive(&self, adaptive: bool) {
+        self.adaptive.store(adaptive, Ordering::Release);
+    }
+
+    pub fn is_adaptive(&self) -> bool {
+        self.adaptive.load(Ordering::Acquire)
+    }
+
+    pub async fn acquire(&self) -> Acquired {
+        self.acquire_n(1).await
+    }
+
+    // takes `n` tokens at once; the request goes out once all `n` would have been available,
+    // so anything costing more than a window's burst always has to wait a bit
+    pub async fn acquire_n(&self, n: u64) -> Acquired {
+        self.acquire_with_priority(n, Priority::Normal).await
+    }
+
+    // waiters are served highest priority first (oldest first within a priority), except that
+    // anyone who's been waiting longer than the starvation limit goes ahead of everybody
+    pub async fn acquire_with_priority(&self, n: u64, priority: Priority) -> Acquired {
+        assert!(n > 0, "can't acquire 0 tokens");
+
+        let started = TokioInstant::now();
+        let mut bottleneck = None;
+        let waiter = self.enqueue(priority);
+
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = self.now();
+
+                if state.next_up(now, self.starvation_limit) != Some(waiter.id) {
+                    bottleneck = Some(Bottleneck::Queued);
+                    // if it's not our turn now, it might become our turn once we've been
+                    // waiting long enough, even if nobody else moves
+                    Wait::Turn(waiter.ages_at(self.starvation_limit).filter(|at| *at > now))
+                } else {
+                    match self.earliest(&state, n, now) {
+                        (at, cause) if at > now => {
+                            bottleneck = cause.or(bottleneck);
+                            Wait::Slot(at)
+                        }
+                        (at, _) => {
+                            self.take(&mut state, n, at);
+                            break;
+                        }
+                    }
+                }
+            };
+
+            // the slot is only looked at again after waking up; a pause or a more important
+            // waiter may have come in in the meantime
+            match wait {
+                Wait::Slot(at) | Wait::Turn(Some(at)) => {
+                    // the clock kept going since the lock was let go, and may already be past `at`
+                    let sleep = tokio::time::sleep(TokioDuration::from_nanos(at.saturating_sub(self.now())));
+                    tokio::select! {
+                        _ = sleep => {}
+                        _ = waiter.notify.notified() => {}
+                    }
+                }
+                Wait::Turn(None) => waiter.notify.notified().await,
+            }
+        }
+
+        // `waiter` leaves the queue and hands the turn over when it's dropped here
+        drop(waiter);
+        self.autosave();
+
+        Acquired {
+            waited: started.elapsed(),
+            bottleneck,
+        }
+    }
+
+    fn enqueue(&self, priority: Priority) -> WaiterGuard<'_> {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_waiter_id;
+        state.next_waiter_id += 1;
+
+        let notify = Arc::new(Notify::new());
+        let since = self.now();
+        state.waiters.push(Waiter { id, priority, since, notify: notify.clone() });
+
+        WaiterGuard { limiter: self, id, since, notify }
+    }
+
+    // finds the earliest time at which every window has `n` tokens
+    fn earliest(&self, state: &State, n: u64, now: u64) -> (u64, Option<Bottleneck>) {
+        let mut at = now;
+        let mut cause = None;
+        let mut raise = |at: &mut u64, to: u64, why: Bottleneck| {
+            if to > *at {
+                *at = to;
+                cause = Some(why);
+            }
+        };
 
-        if slot > now {
-            tokio::time::sleep(TokioDuration::from_nanos(slot - now)).await;
+        raise(&mut at, state.paused_until, Bottleneck::Paused);
+
+        if let Some(throttle) = state.throttle.filter(|t| t.until > now) {
+            raise(&mut at, throttle.next_at, Bottleneck::Throttled);
+        }
+
+        // pushing `at` into a later period can free a fixed window up, so go around until nothing moves
+        for _ in 0..=self.windows.len() {
+            let before = at;
+
+            for (i, (window, window_state)) in self.windows.iter().zip(&state.windows).enumerate() {
+                match (window, window_state) {
+                    (Window::Rate { nanos_per_token, .. }, WindowState::Rate { next_at }) => {
+                        raise(&mut at, next_at + nanos_per_token * (n - 1), Bottleneck::Window(i));
+                    }
+                    (Window::Fixed { limit, period, .. }, WindowState::Fixed { period_index, count }) => {
+                        let index = self.period_index(at, *period);
+                        let count = if index == *period_index { *count } else { 0 };
+
+                        // something bigger than the whole window still gets through on a fresh one
+                        if count > 0 && count + n > *limit {
+                            raise(&mut at, self.period_start(index + 1, *period), Bottleneck::Window(i));
+                        }
+                    }
+                    _ => unreachable!("window states are built from the windows"),
+                }
+            }
+
+            if at == before {
+                break;
+            }
+        }
+
+        (at, cause)
+    }
+
+    // takes `n` tokens from every window at `at`
+    fn take(&self, state: &mut State, n: u64, at: u64) {
+        for (window, window_state) in self.windows.iter().zip(state.windows.iter_mut()) {
+            match (window, window_state) {
+                (Window::Rate { nanos_per_token, burst_nanos, .. }, WindowState::Rate { next_at }) => {
+                    *next_at = (*next_at).max(at.saturating_sub(*burst_nanos)) + nanos_per_token * n;
+                }
+                (Window::Fixed { period, .. }, WindowState::Fixed { period_index, count }) => {
+                    let index = self.period_index(at, *period);
+                    if index != *period_index {
+                        *period_index = index;
+                        *count = 0;
+                    }
+                    *count += n;
+                }
+                _ => unreachable!("window states are built from the windows"),
+            }
+        }
+
+        if let Some(throttle) = state.throttle.as_mut().filter(|t| t.until > at) {
+            throttle.next_at = throttle.next_at.max(at) + throttle.nanos_per_token * n;
+        }
+    }
+
+    // ######## ADAPTIVE ########
+    pub fn pause_for(&self, duration: Duration) {
+        let until = self.now().saturating_add(duration.as_nanos() as u64);
+        let mut state = self.state.lock().unwrap();
+
+        state.paused_until = state.paused_until.max(until);
+
+        // no bursting right after a pause, either
+        for window_state in state.windows.iter_mut() {
+            if let WindowState::Rate { next_at } = window_state {
+                *next_at = (*next_at).max(until);
+            }
+        }
+    }
+
+    pub fn paused_for(&self) -> Option<Duration> {
+        let now = self.now();
+        let until = self.state.lock().unwrap().paused_until;
+        (until > now).then(|| Duration::from_nanos(until - now))
+    }
+
+    pub fn observe(&self, response: &Response) {
+        self.observe_parts(response.status(), response.headers());
+    }
+
+    pub fn observe_parts(&self, status: StatusCode, headers: &HeaderMap) {
+        self.observe_pause(status, headers);
+        self.observe_quota(headers);
+    }
+
+    // only the 429/503 + Retry-After half of observe_parts, for limiters that should stop along
+    // with the one that saw it without taking on another route's quota
+    pub fn observe_pause(&self, status: StatusCode, headers: &HeaderMap) {
+        if !self.is_adaptive() {
+            return;
+        }
+
+        if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+            match parse_retry_after(headers) {
+                Some(retry_after) => self.pause_for(retry_after),
+                // a 503 without Retry-After is just the server being down, not a rate limit
+                None if status == StatusCode::TOO_MANY_REQUESTS => self.pause_for(self.default_retry_after),
+                None => {}
+            }
+        }
+    }
+
+    // the RateLimit / X-RateLimit-* half of observe_parts
+    pub fn observe_quota(&self, headers: &HeaderMap) {
+        if !self.is_adaptive() {
+            return;
+        }
+
+        if let Some(RateLimitInfo { remaining, reset }) = RateLimitInfo::from_headers(headers) {
+            if remaining == 0 {
+                self.pause_for(reset);
+            } else {
+                // spread what's left of the quota over what's left of the window
+                let spacing = (reset.as_nanos() / remaining as u128) as u64;
+                let now = self.now();
+                let mut state = self.state.lock().unwrap();
+
+                state.throttle = Some(Throttle {
+                    nanos_per_token: spacing,
+                    until: now.saturating_add(reset.as_nanos() as u64),
+                    next_at: state.throttle.map(|t| t.next_at).unwrap_or(0),
+                });
+            }
+        }
+    }
+
+    // ######## PERSISTENCE ########
+    // loads whatever was saved at `path` for the persistent windows, and keeps saving there
+    // (at most once per second, plus on drop) so that e.g. a daily quota survives restarts.
+    // a saved window is only restored if it's still configured the same way
+    pub fn persist_to(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        match std::fs::read_to_string(&path) {
+            Ok(saved) => self.load(&saved),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        self.persistence = Some(Persistence {
+            path,
+            interval: Duration::from_secs(1),
+            last_saved: Mutex::new(TokioInstant::now()),
+        });
+
+        Ok(self)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        match self.snapshot() {
+            Some((path, saved)) => std::fs::write(path, saved),
+            None => Ok(()),
+        }
+    }
+
+    // what save writes, and where
+    fn snapshot(&self) -> Option<(PathBuf, String)> {
+        let persistence = self.persistence.as_ref()?;
+
+        let state = self.state.lock().unwrap();
+        let mut out = String::new();
+
+        for (i, (window, window_state)) in self.windows.iter().zip(&state.windows).enumerate() {
+            if !window.is_persistent() {
+                continue;
+            }
+
+            match (window, window_state) {
+                (Window::Rate { nanos_per_token, burst_nanos, .. }, WindowState::Rate { next_at }) => {
+                    let next_at = self.to_unix_nanos(*next_at);
+                    out.push_str(&format!("rate {i} {nanos_per_token} {burst_nanos} {next_at}\n"));
+                }
+                (Window::Fixed { limit, period, .. }, WindowState::Fixed { period_index, count }) => {
+                    let period = period.as_nanos();
+                    out.push_str(&format!("fixed {i} {limit} {period} {period_index} {count}\n"));
+                }
+                _ => unreachable!("window states are built from the windows"),
+            }
+        }
+
+        drop(state);
+        Some((persistence.path.clone(), out))
+    }
+
+    fn load(&mut self, saved: &str) {
+        let mut state = self.state.lock().unwrap();
+
+        for line in saved.lines() {
+            let parts = line.split_whitespace().collect::<Vec<_>>();
+            let numbers = parts.iter().skip(1).map(|p| p.parse::<u128>().ok()).collect::<Option<Vec<_>>>();
+
+            let (Some(kind), Some(numbers)) = (parts.first(), numbers) else {
+                continue;
+            };
+
+            let Some(i) = numbers.first().map(|i| *i as usize) else {
+                continue;
+            };
+
+            match (*kind, self.windows.get(i), state.windows.get_mut(i), &numbers[1..]) {
+                (
+                    "rate",
+                    Some(Window::Rate { nanos_per_token, burst_nanos, .. }),
+                    Some(WindowState::Rate { next_at }),
+                    &[saved_npt, saved_burst, saved_next],
+                ) if saved_npt == *nanos_per_token as u128 && saved_burst == *burst_nanos as u128 => {
+                    *next_at = self.unix_nanos_to_at(saved_next);
+                }
+                (
+                    "fixed",
+                    Some(Window::Fixed { limit, period, .. }),
+                    Some(WindowState::Fixed { period_index, count }),
+                    &[saved_limit, saved_period, saved_index, saved_count],
+                ) if saved_limit == *limit as u128 && saved_period == period.as_nanos() => {
+                    *period_index = saved_index as u64;
+                    *count = saved_count as u64;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn autosave(&self) {
+        let Some(persistence) = &self.persistence else {
+            return;
+        };
+
+        {
+            let mut last_saved = persistence.last_saved.lock().unwrap();
+            if last_saved.elapsed() < persistence.interval {
+                return;
+            }
+            *last_saved = TokioInstant::now();
+        }
+
+        let Some((path, saved)) = self.snapshot() else {
+            return;
+        };
+
+        // best effort: a failed save only means a bit of quota might be forgotten on restart.
+        // this runs inside acquire, so the write is kept off the runtime's worker threads
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(move || std::fs::write(path, saved));
+            }
+            Err(_) => {
+                let _ = std::fs::write(path, saved);
+            }
+        }
+    }
+
+    // ######## TIME ########
+    #[inline]
+    fn now(&self) -> u64 {
+        (self.start.elapsed().as_nanos() as u64).saturating_add(ORIGIN)
+    }
+
+    fn to_unix_nanos(&self, at: u64) -> u128 {
+        (self.start_unix_nanos + at as u128).saturating_sub(ORIGIN as u128)
+    }
+
+    // times before this process started land below ORIGIN, so a window that refilled while we
+    // were down gets its whole burst back
+    fn unix_nanos_to_at(&self, unix: u128) -> u64 {
+        match unix.checked_sub(self.start_unix_nanos) {
+            Some(since_start) => (since_start as u64).saturating_add(ORIGIN),
+            None => ORIGIN.saturating_sub((self.start_unix_nanos - unix) as u64),
         }
     }
-}
\ No newline at end of file
+
+    fn period_index(&self, at: u64, period: Duration) -> u64 {
+        (self.to_unix_nanos(at) / period.as_nanos()) as u64
+    }
+
+    fn period_start(&self, index: u64, period: Duration) -> u64 {
+        self.unix_nanos_to_at(index as u128 * period.as_nanos())
+    }
+}
+
+impl Drop for RateLimiter {
+    fn drop(&mut self) {
+        let _ = self.save();
+    }
+}
+
+fn unix_nanos_now() -> u128 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+// either delay-seconds or an HTTP-date
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitInfo {
+    pub remaining: u64,
+    pub reset: Duration,
+}
+
+impl RateLimitInfo {
+    // understands, in order:
+    // - `RateLimit: limit=100, remaining=50, reset=30` and `RateLimit: "default";r=50;t=30` (IETF drafts)
+    // - `RateLimit-Remaining` + `RateLimit-Reset` (older IETF drafts)
+    // - `X-RateLimit-Remaining` + `X-RateLimit-Reset` (de facto, reset is either delta seconds or a unix timestamp)
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        if let Some(info) = headers
+            .get("ratelimit")
+            .and_then(|v| v.to_str().ok())
+            .and_then(Self::from_structured)
+        {
+            return Some(info);
+        }
+
+        for (remaining, reset) in [
+            ("ratelimit-remaining", "ratelimit-reset"),
+            ("x-ratelimit-remaining", "x-ratelimit-reset"),
+        ] {
+            let remaining = header_u64(headers, remaining);
+            let reset = header_u64(headers, reset);
+
+            if let (Some(remaining), Some(reset)) = (remaining, reset) {
+                return Some(Self { remaining, reset: reset_to_duration(reset) });
+            }
+        }
+
+        None
+    }
+
+    fn from_structured(value: &str) -> Option<Self> {
+        let mut remaining = None;
+        let mut reset = None;
+
+        for param in value.split([',', ';']) {
+            let Some((key, value)) = param.split_once('=') else {
+                continue;
+            };
+
+            let value = value.trim().trim_matches('"').parse::<u64>().ok();
+            match key.trim() {
+                "remaining" | "r" => remaining = value,
+                "reset" | "t" => reset = value,
+                _ => {}
+            }
+        }
+
+        Some(Self { remaining: remaining?, reset: Duration::from_secs(reset?) })
+    }
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    let value = headers.get(name)?.to_str().ok()?.trim();
+    // some apis send the reset as a float
+    value.parse::<u64>().ok().or_else(|| value.parse::<f64>().ok().map(|f| f.ceil() as u64))
+}
+
+// nobody's window is 30+ years long, so anything that big must be a unix timestamp
+fn reset_to_duration(reset: u64) -> Duration {
+    const TIMESTAMP_THRESHOLD: u64 = 1_000_000_000;
+
+    if reset < TIMESTAMP_THRESHOLD {
+        return Duration::from_secs(reset);
+    }
+
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    Duration::from_secs(reset.saturating_sub(now))
+}
@@ -6,8 +6,7 @@ use http::{HeaderName, HeaderValue};
 use reqwest::Response;
 
 use crate::{
-    handlers::{BaseHandler, Handler},
-    net::{Client, net_error::NetError}, resources::resource_handler::ResourceManager,
+    net::{Client, concurrency::ConcurrencyLimiter, hosts::HostRoute, net_error::NetError, rate_limiter::{Priority, RateLimiter}}, resources::resource_handler::ResourceManager,
 };
 
 // pub struct RequestRunner<H: Handler> {
@@ -21,9 +20,44 @@ use crate::{
 //     }
 // }
 
+// what the Client needs to know about a request besides the request itself;
+// carried from the RequestBuilder over to the Request
+#[derive(Debug, Clone)]
+pub struct RequestMeta {
+    // Record's and Endpoint's own limiters, in that order; the Client's is always applied on top
+    pub rate_limiters: Vec<Arc<RateLimiter>>,
+    // how many tokens this request takes from every rate limiter it goes through
+    pub cost: u64,
+    pub priority: Priority,
+    // set when the Record has mirrors; the request is built for the first host and moved
+    // to whichever the pool picks when it's sent
+    pub hosts: Option<HostRoute>,
+    // the Record's, if it has one; the Client's is always applied on top
+    pub concurrency_limiter: Option<Arc<ConcurrencyLimiter>>,
+    // whether sending this twice is harmless; Endpoints default to their HttpVerb's
+    pub idempotent: bool,
+    // set by the IdempotencyKey capability, and carried over to every retry of the request
+    pub idempotency_key: Option<String>,
+}
+
+impl Default for RequestMeta {
+    fn default() -> Self {
+        Self {
+            rate_limiters: Vec::new(),
+            cost: 1,
+            priority: Priority::Normal,
+            hosts: None,
+            concurrency_limiter: None,
+            idempotent: false,
+            idempotency_key: None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct RequestBuilder {
     pub client: Client,
+    pub meta: RequestMeta,
     pub(super) inner: reqwest::RequestBuilder,
 }
 
@@ -32,26 +66,36 @@ impl RequestBuilder {
         self.client.resource_manager.clone()
     }
 
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.meta.priority = priority;
+        self
+    }
+
+    pub fn idempotent(mut self, idempotent: bool) -> Self {
+        self.meta.idempotent = idempotent;
+        self
+    }
+
     delegate! {
         to self.inner {
-            #[expr(Self { inner: $, client: self.client })]
+            #[expr(Self { inner: $, client: self.client, meta: self.meta })]
             pub fn basic_auth<U, P>(self, username: U, password: Option<P>) -> RequestBuilder
             where
                 U: std::fmt::Display,
                 P: std::fmt::Display;
 
-            #[expr(Self { inner: $, client: self.client })]
+            #[expr(Self { inner: $, client: self.client, meta: self.meta })]
             pub fn bearer_auth<T>(self, token: T) -> RequestBuilder
             where
                 T: std::fmt::Display;
 
-            #[expr(Self { inner: $, client: self.client })]
+            #[expr(Self { inner: $, client: self.client, meta: self.meta })]
             pub fn body<T>(self, body: T) -> RequestBuilder
             where
                 T: Into<reqwest::Body>;
 
 
-            #[expr(Self { inner: $, client: self.client })]
+            #[expr(Self { inner: $, client: self.client, meta: self.meta })]
             pub fn header<K, V>(self, key: K, value: V) -> RequestBuilder
             where
                 HeaderName: TryFrom<K>,
@@ -59,29 +103,29 @@ impl RequestBuilder {
                 HeaderValue: TryFrom<V>,
                 <HeaderValue as TryFrom<V>>::Error: Into<http::Error>;
 
-            #[expr(Self { inner: $, client: self.client })]
+            #[expr(Self { inner: $, client: self.client, meta: self.meta })]
             pub fn headers(self, headers: reqwest::header::HeaderMap) -> RequestBuilder;
 
             #[cfg(feature = "reqwest-query")]
-            #[expr(Self { inner: $, client: self.client })]
+            #[expr(Self { inner: $, client: self.client, meta: self.meta })]
             pub fn query<T: ?Sized + serde::Serialize>(self, query: &T) -> RequestBuilder;
 
             #[cfg(feature = "reqwest-form")]
-            #[expr(Self { inner: $, client: self.client })]
+            #[expr(Self { inner: $, client: self.client, meta: self.meta })]
             pub fn form<T: ?Sized + serde::Serialize>(self, form: &T) -> RequestBuilder;
 
             #[cfg(feature = "reqwest-json")]
-            #[expr(Self { inner: $, client: self.client })]
+            #[expr(Self { inner: $, client: self.client, meta: self.meta })]
             pub fn json<T: ?Sized + serde::Serialize>(self, json: &T) -> RequestBuilder;
 
             #[cfg(feature = "reqwest-multipart")]
-            #[expr(Self { inner: $, client: self.client })]
+            #[expr(Self { inner: $, client: self.client, meta: self.meta })]
             pub fn multipart(self, form: reqwest::multipart::Form) -> RequestBuilder;
 
-            #[expr(Self { inner: $, client: self.client })]
+            #[expr(Self { inner: $, client: self.client, meta: self.meta })]
             pub fn version(self, version: reqwest::Version) -> RequestBuilder;
 
-            #[expr(Self { inner: $, client: self.client })]
+            #[expr(Self { inner: $, client: self.client, meta: self.meta })]
             pub fn timeout(self, timeout: std::time::Duration) -> RequestBuilder;
 
         }
@@ -90,13 +134,14 @@ impl RequestBuilder {
     pub fn build(self) -> Result<Request, NetError> {
         Ok(Request {
             inner: self.inner.build()?,
-            client: self.client
+            client: self.client,
+            meta: self.meta,
         })
     }
 
     pub async fn send(self) -> Result<Response, NetError> {
-        let build = self.inner.build()?;
-        self.client.execute_reqwest_req(build).await
+        let request = self.build()?;
+        request.client.clone().execute_request(request).await
     }
 }
 
@@ -105,12 +150,14 @@ impl RequestBuilder {
 pub struct Request {
     pub(crate) inner: reqwest::Request,
     pub client: Client,
+    pub meta: RequestMeta,
 }
 
 impl fmt::Debug for Request {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Request")
             .field("inner", &self.inner)
+            .field("meta", &self.meta)
             .field("rate_limiter", &"async_rate_limiter internals")
             .finish()
     }
@@ -138,7 +185,7 @@ impl Request {
             pub fn version_mut(&mut self) -> &mut reqwest::Version;
 
             
-            #[expr(Some(Self { inner: $?, client: self.client.clone() }))]
+            #[expr(Some(Self { inner: $?, client: self.client.clone(), meta: self.meta.clone() }))]
             pub fn try_clone(&self) -> Option<Request>;
         }
     }
@@ -146,6 +193,12 @@ impl Request {
     pub fn get_client(&self) -> &Client {
         &self.client
     }
+
+    // whether it's fine to send this again: either it's idempotent, or the server can tell
+    // the repeats apart by their Idempotency-Key
+    pub fn is_retry_safe(&self) -> bool {
+        self.meta.idempotent || self.meta.idempotency_key.is_some()
+    }
 }
 
 impl TryFrom<Request> for http::Request<reqwest::Body> {
@@ -0,0 +1,81 @@
+use std::{fmt::Debug, sync::Arc};
+
+#[cfg(not(feature = "async-trait"))]
+use std::pin::Pin;
+
+use reqwest::{Client as ReqClient, Response, ResponseBuilderExt, Url};
+
+use crate::net::net_error::NetError;
+
+pub type TransportResult = Result<Response, NetError>;
+
+#[cfg(not(feature = "async-trait"))]
+pub struct TransportOutput<'a>(pub Pin<Box<dyn Future<Output = TransportResult> + Send + 'a>>);
+
+#[cfg(not(feature = "async-trait"))]
+impl<'a> TransportOutput<'a> {
+    pub fn new(fut: impl Future<Output = TransportResult> + Send + 'a) -> Self {
+        Self(Box::pin(fut))
+    }
+}
+
+#[cfg(not(feature = "async-trait"))]
+impl<'a> Future for TransportOutput<'a> {
+    type Output = TransportResult;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<Self::Output> {
+        self.0.as_mut().poll(cx)
+    }
+}
+
+// whatever actually sends the request; reqwest::Client by default, but anything that can
+// turn a request into a response works (see provided::transports)
+#[cfg(not(feature = "async-trait"))]
+pub trait Transport: Debug + Send + Sync {
+    fn execute<'a>(&'a self, request: reqwest::Request) -> TransportOutput<'a>;
+}
+
+#[cfg(feature = "async-trait")]
+#[async_trait::async_trait]
+pub trait Transport: Debug + Send + Sync {
+    async fn execute(&self, request: reqwest::Request) -> TransportResult;
+}
+
+#[cfg(not(feature = "async-trait"))]
+impl Transport for ReqClient {
+    fn execute<'a>(&'a self, request: reqwest::Request) -> TransportOutput<'a> {
+        TransportOutput::new(async move { Ok(ReqClient::execute(self, request).await?) })
+    }
+}
+
+#[cfg(feature = "async-trait")]
+#[async_trait::async_trait]
+impl Transport for ReqClient {
+    async fn execute(&self, request: reqwest::Request) -> TransportResult {
+        Ok(ReqClient::execute(self, request).await?)
+    }
+}
+
+#[cfg(not(feature = "async-trait"))]
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn execute<'a>(&'a self, request: reqwest::Request) -> TransportOutput<'a> {
+        (**self).execute(request)
+    }
+}
+
+#[cfg(feature = "async-trait")]
+#[async_trait::async_trait]
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    async fn execute(&self, request: reqwest::Request) -> TransportResult {
+        (**self).execute(request).await
+    }
+}
+
+// reqwest only lets a Response know its url through http::response::Builder, so this
+// borrows the extension from a throwaway builder
+pub(crate) fn response_from_parts(mut parts: http::response::Parts, body: reqwest::Body, url: Url) -> Response {
+    let with_url = http::Response::builder().url(url).body(()).unwrap();
+    parts.extensions.extend(with_url.into_parts().0.extensions);
+
+    http::Response::from_parts(parts, body).into()
+}
@@ -0,0 +1,38 @@
+#[cfg(not(feature = "async-trait"))]
+use std::future::ready;
+
+use http::{HeaderValue, header::ACCEPT};
+
+#[cfg(not(feature = "async-trait"))]
+use crate::capability::CapabilityOutput;
+use crate::{
+    capability::{CapError, Capability},
+    net::RequestBuilder,
+};
+
+// sets the Accept header; ByContentType::accept builds one that matches its decoders
+#[derive(Debug, Clone)]
+pub struct Accept(pub String);
+
+impl Accept {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    fn add_to(&self, request: RequestBuilder) -> Result<RequestBuilder, CapError> {
+        Ok(request.header(ACCEPT, HeaderValue::from_str(&self.0)?))
+    }
+}
+
+#[cfg_attr(feature = "async-trait", async_trait::async_trait)]
+impl Capability for Accept {
+    #[cfg(not(feature = "async-trait"))]
+    fn apply<'a>(&'a self, request: RequestBuilder) -> CapabilityOutput<'a> {
+        CapabilityOutput::new(ready(self.add_to(request)))
+    }
+
+    #[cfg(feature = "async-trait")]
+    async fn apply(&self, request: RequestBuilder) -> Result<RequestBuilder, CapError> {
+        self.add_to(request)
+    }
+}
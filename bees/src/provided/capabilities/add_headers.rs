@@ -1,4 +1,6 @@
-use std::{future::ready, str::FromStr};
+#[cfg(not(feature = "async-trait"))]
+use std::future::ready;
+use std::str::FromStr;
 
 use http::{HeaderMap, HeaderName, HeaderValue};
 
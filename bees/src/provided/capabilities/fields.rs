@@ -0,0 +1,97 @@
+// what #[derive(Endpoint)] generates for #[header] and #[body] fields. unlike AddHeaders and
+// JsonBody, values are used as they are: no <resource> interpolation on user data
+use std::fmt;
+#[cfg(not(feature = "async-trait"))]
+use std::future::ready;
+
+use http::{HeaderMap, HeaderName, HeaderValue, header::CONTENT_TYPE};
+
+#[cfg(not(feature = "async-trait"))]
+use crate::capability::CapabilityOutput;
+use crate::{
+    capability::{CapError, Capability},
+    net::{RequestBuilder, bodies::BodyAdder},
+};
+
+pub struct FieldHeaders(pub Vec<(&'static str, String)>);
+
+impl FieldHeaders {
+    fn header_map(&self) -> Result<HeaderMap, CapError> {
+        let mut header_map = HeaderMap::new();
+
+        for (name, value) in &self.0 {
+            header_map.append(HeaderName::from_bytes(name.as_bytes())?, HeaderValue::from_str(value)?);
+        }
+
+        Ok(header_map)
+    }
+}
+
+#[cfg_attr(feature = "async-trait", async_trait::async_trait)]
+impl Capability for FieldHeaders {
+    #[cfg(not(feature = "async-trait"))]
+    fn apply<'a>(&'a self, request: RequestBuilder) -> CapabilityOutput<'a> {
+        CapabilityOutput::new(ready(self.header_map().map(|map| request.headers(map))))
+    }
+
+    #[cfg(feature = "async-trait")]
+    async fn apply(&self, request: RequestBuilder) -> Result<RequestBuilder, CapError> {
+        self.header_map().map(|map| request.headers(map))
+    }
+}
+
+// serialized when the request is built, so the field doesn't have to outlive the call;
+// a serialization error comes out of `apply`
+pub struct PreparedBody {
+    content_type: &'static str,
+    bytes: Result<Vec<u8>, String>,
+}
+
+impl PreparedBody {
+    #[cfg(feature = "reqwest-json")]
+    pub fn json<T: serde::Serialize + ?Sized>(value: &T) -> Self {
+        Self {
+            content_type: "application/json",
+            bytes: serde_json::to_vec(value).map_err(|e| format!("couldn't serialize the body as json: {e}")),
+        }
+    }
+
+    #[cfg(feature = "reqwest-form")]
+    pub fn form<T: serde::Serialize + ?Sized>(value: &T) -> Self {
+        Self {
+            content_type: "application/x-www-form-urlencoded",
+            bytes: serde_urlencoded::to_string(value)
+                .map(String::into_bytes)
+                .map_err(|e| format!("couldn't serialize the body as a form: {e}")),
+        }
+    }
+
+    fn add_to(&self, request: RequestBuilder) -> Result<RequestBuilder, CapError> {
+        let bytes = self.bytes.clone()?;
+        Ok(request.header(CONTENT_TYPE, self.content_type).body(bytes))
+    }
+}
+
+impl fmt::Debug for PreparedBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PreparedBody")
+            .field("content_type", &self.content_type)
+            .field("len", &self.bytes.as_ref().map(Vec::len))
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "async-trait", async_trait::async_trait)]
+impl Capability for PreparedBody {
+    #[cfg(not(feature = "async-trait"))]
+    fn apply<'a>(&'a self, request: RequestBuilder) -> CapabilityOutput<'a> {
+        CapabilityOutput::new(ready(self.add_to(request)))
+    }
+
+    #[cfg(feature = "async-trait")]
+    async fn apply(&self, request: RequestBuilder) -> Result<RequestBuilder, CapError> {
+        self.add_to(request)
+    }
+}
+
+impl BodyAdder for PreparedBody {}
@@ -0,0 +1,72 @@
+#[cfg(not(feature = "async-trait"))]
+use std::future::ready;
+
+use http::{HeaderMap, HeaderName, HeaderValue};
+
+#[cfg(not(feature = "async-trait"))]
+use crate::capability::CapabilityOutput;
+use crate::{
+    capability::{CapError, Capability},
+    net::RequestBuilder,
+    utils::random::random_uuid,
+};
+
+// gives every call its own Idempotency-Key. capabilities run once per call and Retries resends
+// clones of the built Request, so all attempts of a call share the key, and Retries will retry
+// POSTs and PATCHes that carry one. a call that already has a key (from an earlier capability,
+// e.g. a client-wide one before the Record's) keeps it and its header, so it's only sent once
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey {
+    header: HeaderName,
+    generate: fn() -> String,
+}
+
+impl IdempotencyKey {
+    pub fn new() -> Self {
+        Self { header: HeaderName::from_static("idempotency-key"), generate: random_uuid }
+    }
+
+    // for apis that want it under another name, e.g. X-Idempotency-Key
+    pub fn header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn generator(mut self, generate: fn() -> String) -> Self {
+        self.generate = generate;
+        self
+    }
+
+    fn add_to(&self, request: RequestBuilder) -> Result<RequestBuilder, CapError> {
+        if request.meta.idempotency_key.is_some() {
+            return Ok(request);
+        }
+
+        let key = (self.generate)();
+
+        // headers replaces whatever is already there under that name, where header would append
+        let header = HeaderMap::from_iter([(self.header.clone(), HeaderValue::from_str(&key)?)]);
+        let mut request = request.headers(header);
+        request.meta.idempotency_key = Some(key);
+        Ok(request)
+    }
+}
+
+impl Default for IdempotencyKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(feature = "async-trait", async_trait::async_trait)]
+impl Capability for IdempotencyKey {
+    #[cfg(not(feature = "async-trait"))]
+    fn apply<'a>(&'a self, request: RequestBuilder) -> CapabilityOutput<'a> {
+        CapabilityOutput::new(ready(self.add_to(request)))
+    }
+
+    #[cfg(feature = "async-trait")]
+    async fn apply(&self, request: RequestBuilder) -> Result<RequestBuilder, CapError> {
+        self.add_to(request)
+    }
+}
@@ -1 +1,4 @@
-pub mod add_headers;
\ No newline at end of file
+pub mod add_headers;
+pub mod fields;
+pub mod accept;
+pub mod idempotency;
\ No newline at end of file
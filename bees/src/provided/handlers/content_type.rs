@@ -0,0 +1,147 @@
+use std::{any::type_name, fmt};
+
+use http::header::CONTENT_TYPE;
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+
+use super::{DecodeError, DecodeSource, Format, decode_response};
+use crate::{handlers::Handler, provided::capabilities::accept::Accept};
+
+struct Decoder<T> {
+    name: &'static str,
+    media_types: &'static [&'static str],
+    suffix: Option<&'static str>,
+    decode: fn(&[u8]) -> Result<T, DecodeSource>,
+}
+
+impl<T> Decoder<T> {
+    fn matches(&self, media_type: &str) -> bool {
+        self.media_types.iter().any(|m| m.eq_ignore_ascii_case(media_type))
+            || self.suffix.is_some_and(|suffix| media_type.ends_with(suffix))
+    }
+}
+
+// picks the decoder by the response's Content-Type. decoders are tried in the order they were
+// added, and a response without a Content-Type goes to the first one
+pub struct ByContentType<T> {
+    decoders: Vec<Decoder<T>>,
+}
+
+impl<T> ByContentType<T> {
+    // no decoders at all; Default has every Format that's compiled in
+    pub fn new() -> Self {
+        Self { decoders: Vec::new() }
+    }
+
+    pub fn format<F: Format>(self) -> Self
+    where
+        T: DeserializeOwned,
+    {
+        self.decoder(F::NAME, F::MEDIA_TYPES, F::SUFFIX, F::decode::<T>)
+    }
+
+    // text/plain, taken as it is
+    pub fn text(self) -> Self
+    where
+        T: From<String>,
+    {
+        self.decoder("text", &["text/plain"], None, |body| Ok(String::from_utf8(body.to_vec())?.into()))
+    }
+
+    pub fn decoder(
+        mut self,
+        name: &'static str,
+        media_types: &'static [&'static str],
+        suffix: Option<&'static str>,
+        decode: fn(&[u8]) -> Result<T, DecodeSource>,
+    ) -> Self {
+        self.decoders.push(Decoder { name, media_types, suffix, decode });
+        self
+    }
+
+    // an Accept that asks for the media types of the decoders, in the order they were added
+    pub fn accept(&self) -> Accept {
+        let mut accept = Vec::new();
+
+        for (i, decoder) in self.decoders.iter().enumerate() {
+            let quality = 10usize.saturating_sub(i).max(1);
+            for media_type in decoder.media_types {
+                match quality {
+                    10 => accept.push(media_type.to_string()),
+                    q => accept.push(format!("{media_type};q=0.{q}")),
+                }
+            }
+        }
+
+        Accept::new(accept.join(", "))
+    }
+}
+
+impl<T: DeserializeOwned> Default for ByContentType<T> {
+    #[allow(unused_mut)]
+    fn default() -> Self {
+        let mut by_content_type = Self::new();
+
+        #[cfg(feature = "reqwest-json")]
+        {
+            by_content_type = by_content_type.format::<super::Json>();
+        }
+        #[cfg(feature = "decode-xml")]
+        {
+            by_content_type = by_content_type.format::<super::Xml>();
+        }
+        #[cfg(feature = "decode-cbor")]
+        {
+            by_content_type = by_content_type.format::<super::Cbor>();
+        }
+        #[cfg(feature = "decode-msgpack")]
+        {
+            by_content_type = by_content_type.format::<super::MsgPack>();
+        }
+        #[cfg(feature = "decode-form")]
+        {
+            by_content_type = by_content_type.format::<super::Form>();
+        }
+
+        by_content_type
+    }
+}
+
+impl<T> fmt::Debug for ByContentType<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ByContentType")
+            .field("output", &type_name::<T>())
+            .field("decoders", &self.decoders.iter().map(|d| d.name).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<T: Send> Handler for ByContentType<T> {
+    type Input = Response;
+
+    type Output = Result<T, DecodeError>;
+
+    async fn execute(
+        &self,
+        input: Self::Input,
+    ) -> Self::Output {
+        let media_type = input
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.split(';').next().unwrap_or_default().trim().to_ascii_lowercase());
+
+        let decoder = match &media_type {
+            Some(media_type) => self.decoders.iter().find(|decoder| decoder.matches(media_type)),
+            None => self.decoders.first(),
+        };
+
+        match decoder {
+            Some(decoder) => decode_response(input, decoder.name, decoder.decode).await,
+            None => {
+                let source = format!("no decoder for the content type {:?}", media_type.unwrap_or_default());
+                decode_response(input, "content type", |_| Err::<T, _>(source)).await
+            }
+        }
+    }
+}
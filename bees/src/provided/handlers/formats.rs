@@ -0,0 +1,182 @@
+use std::{any::type_name, fmt, marker::PhantomData};
+
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+
+use super::{DecodeError, DecodeSource, decode_response};
+use crate::handlers::Handler;
+
+// a body format serde can decode from
+pub trait Format: Send + Sync + 'static {
+    // for errors
+    const NAME: &'static str;
+    // the Content-Types it decodes, the first one being what goes in Accept
+    const MEDIA_TYPES: &'static [&'static str];
+    // structured syntax suffix (RFC 6839), so that e.g. application/vnd.api+json is json too
+    const SUFFIX: Option<&'static str> = None;
+
+    fn decode<T: DeserializeOwned>(body: &[u8]) -> Result<T, DecodeSource>;
+}
+
+#[cfg(feature = "reqwest-json")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+#[cfg(feature = "reqwest-json")]
+impl Format for Json {
+    const NAME: &'static str = "json";
+    const MEDIA_TYPES: &'static [&'static str] = &["application/json"];
+    const SUFFIX: Option<&'static str> = Some("+json");
+
+    fn decode<T: DeserializeOwned>(body: &[u8]) -> Result<T, DecodeSource> {
+        // so that a 204 can still be decoded into () or an Option
+        let body: &[u8] = match body.is_empty() {
+            true => b"null",
+            false => body,
+        };
+
+        Ok(serde_json::from_slice(body)?)
+    }
+}
+
+#[cfg(feature = "decode-xml")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Xml;
+
+#[cfg(feature = "decode-xml")]
+impl Format for Xml {
+    const NAME: &'static str = "xml";
+    const MEDIA_TYPES: &'static [&'static str] = &["application/xml", "text/xml"];
+    const SUFFIX: Option<&'static str> = Some("+xml");
+
+    fn decode<T: DeserializeOwned>(body: &[u8]) -> Result<T, DecodeSource> {
+        Ok(quick_xml::de::from_str(std::str::from_utf8(body)?)?)
+    }
+}
+
+#[cfg(feature = "decode-cbor")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cbor;
+
+#[cfg(feature = "decode-cbor")]
+impl Format for Cbor {
+    const NAME: &'static str = "cbor";
+    const MEDIA_TYPES: &'static [&'static str] = &["application/cbor"];
+    const SUFFIX: Option<&'static str> = Some("+cbor");
+
+    fn decode<T: DeserializeOwned>(body: &[u8]) -> Result<T, DecodeSource> {
+        Ok(ciborium::from_reader(body)?)
+    }
+}
+
+#[cfg(feature = "decode-msgpack")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MsgPack;
+
+#[cfg(feature = "decode-msgpack")]
+impl Format for MsgPack {
+    const NAME: &'static str = "msgpack";
+    const MEDIA_TYPES: &'static [&'static str] = &["application/msgpack", "application/x-msgpack", "application/vnd.msgpack"];
+
+    fn decode<T: DeserializeOwned>(body: &[u8]) -> Result<T, DecodeSource> {
+        Ok(rmp_serde::from_slice(body)?)
+    }
+}
+
+#[cfg(feature = "decode-form")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Form;
+
+#[cfg(feature = "decode-form")]
+impl Format for Form {
+    const NAME: &'static str = "urlencoded";
+    const MEDIA_TYPES: &'static [&'static str] = &["application/x-www-form-urlencoded"];
+
+    fn decode<T: DeserializeOwned>(body: &[u8]) -> Result<T, DecodeSource> {
+        Ok(serde_urlencoded::from_bytes(body)?)
+    }
+}
+
+// decodes a Response's body into T with F
+pub struct Decode<F, T>(PhantomData<fn() -> (F, T)>);
+
+#[cfg(feature = "reqwest-json")]
+pub type IntoJson<T = serde_json::Value> = Decode<Json, T>;
+#[cfg(feature = "decode-xml")]
+pub type IntoXml<T> = Decode<Xml, T>;
+#[cfg(feature = "decode-cbor")]
+pub type IntoCbor<T> = Decode<Cbor, T>;
+#[cfg(feature = "decode-msgpack")]
+pub type IntoMsgPack<T> = Decode<MsgPack, T>;
+#[cfg(feature = "decode-form")]
+pub type IntoForm<T> = Decode<Form, T>;
+
+impl<F, T> Decode<F, T> {
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<F, T> Default for Decode<F, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Format, T> fmt::Debug for Decode<F, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Decode").field(&F::NAME).field(&type_name::<T>()).finish()
+    }
+}
+
+impl<F: Format, T: DeserializeOwned + Send> Handler for Decode<F, T> {
+    type Input = Response;
+
+    type Output = Result<T, DecodeError>;
+
+    async fn execute(
+        &self,
+        input: Self::Input,
+    ) -> Self::Output {
+        decode_response(input, F::NAME, F::decode).await
+    }
+}
+
+// one T per row; the first row is taken as the header
+#[cfg(feature = "decode-csv")]
+pub struct IntoCsv<T>(PhantomData<fn() -> T>);
+
+#[cfg(feature = "decode-csv")]
+impl<T> IntoCsv<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[cfg(feature = "decode-csv")]
+impl<T> Default for IntoCsv<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "decode-csv")]
+impl<T> fmt::Debug for IntoCsv<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IntoCsv").field(&type_name::<T>()).finish()
+    }
+}
+
+#[cfg(feature = "decode-csv")]
+impl<T: DeserializeOwned + Send> Handler for IntoCsv<T> {
+    type Input = Response;
+
+    type Output = Result<Vec<T>, DecodeError>;
+
+    async fn execute(
+        &self,
+        input: Self::Input,
+    ) -> Self::Output {
+        decode_response(input, "csv", |body| csv::Reader::from_reader(body).deserialize().collect::<Result<Vec<T>, _>>()).await
+    }
+}
@@ -0,0 +1,108 @@
+use std::error::Error as StdError;
+
+use derive_more::{Display, Error};
+use reqwest::{Response, StatusCode, Url};
+
+use crate::handlers::Handler;
+
+// the handlers that decode with serde, which come with any of the formats
+macro_rules! with_serde {
+    ($($item:item)*) => {
+        $(
+            #[cfg(any(
+                feature = "reqwest-json",
+                feature = "decode-xml",
+                feature = "decode-cbor",
+                feature = "decode-msgpack",
+                feature = "decode-csv",
+                feature = "decode-form",
+            ))]
+            $item
+        )*
+    };
+}
+
+with_serde! {
+    pub mod formats;
+    pub mod content_type;
+    pub mod status;
+
+    pub use formats::*;
+    pub use content_type::*;
+    pub use status::*;
+}
+
+#[cfg(feature = "reqwest-json")]
+pub mod typed_json;
+#[cfg(feature = "reqwest-json")]
+pub mod problem;
+#[cfg(feature = "reqwest-json")]
+pub use typed_json::*;
+#[cfg(feature = "reqwest-json")]
+pub use problem::*;
+
+// how much of a body a DecodeError keeps around
+const SNIPPET_LEN: usize = 512;
+
+pub type DecodeSource = Box<dyn StdError + Send + Sync>;
+
+// what every provided decoder fails with; the status, url and start of the body are usually
+// enough to tell an error page apart from a schema mismatch
+#[derive(Debug, Display, Error)]
+#[display("couldn't decode the {status} response from {url} as {format}: {source} (body: {snippet:?})")]
+pub struct DecodeError {
+    pub format: &'static str,
+    pub status: StatusCode,
+    pub url: Url,
+    // lossily turned into text, and cut at SNIPPET_LEN bytes
+    pub snippet: String,
+    #[error(source)]
+    pub source: DecodeSource,
+}
+
+impl DecodeError {
+    pub fn new(format: &'static str, status: StatusCode, url: Url, body: &[u8], source: impl Into<DecodeSource>) -> Self {
+        let snippet = String::from_utf8_lossy(&body[..body.len().min(SNIPPET_LEN)]).into_owned();
+
+        Self {
+            format,
+            status,
+            url,
+            snippet,
+            source: source.into(),
+        }
+    }
+}
+
+// reads the whole body and hands it to `decode`; failing to read it is a DecodeError too
+pub async fn decode_response<T, E: Into<DecodeSource>>(
+    response: Response,
+    format: &'static str,
+    decode: impl FnOnce(&[u8]) -> Result<T, E>,
+) -> Result<T, DecodeError> {
+    let status = response.status();
+    let url = response.url().clone();
+
+    let body = match response.bytes().await {
+        Ok(body) => body,
+        Err(e) => return Err(DecodeError::new(format, status, url, &[], e)),
+    };
+
+    decode(&body).map_err(|e| DecodeError::new(format, status, url, &body, e))
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IntoText;
+
+impl Handler for IntoText {
+    type Input = Response;
+
+    type Output = Result<String, DecodeError>;
+
+    async fn execute(
+        &self,
+        req: Self::Input,
+    ) -> Self::Output {
+        decode_response(req, "text", |body| String::from_utf8(body.to_vec())).await
+    }
+}
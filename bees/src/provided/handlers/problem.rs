@@ -0,0 +1,109 @@
+use std::fmt;
+
+use http::header::CONTENT_TYPE;
+use reqwest::Response;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+
+use super::{DecodeSource, Format, Json, StatusError, decode_response};
+use crate::handlers::Handler;
+
+// an RFC 7807 problem details object (application/problem+json)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    // a URI identifying the kind of problem; about:blank when the server leaves it out
+    #[serde(rename = "type", default = "about_blank")]
+    pub problem_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    // the server's copy of the response status
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    // every other member, as the api defines them
+    #[serde(flatten)]
+    pub extensions: Map<String, Value>,
+}
+
+fn about_blank() -> String {
+    "about:blank".to_string()
+}
+
+impl ProblemDetails {
+    // an extension member, decoded into whatever the api documents it as
+    pub fn extension<T: DeserializeOwned>(&self, name: &str) -> Option<Result<T, serde_json::Error>> {
+        self.extensions.get(name).map(|value| T::deserialize(value))
+    }
+}
+
+impl fmt::Display for ProblemDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.title.as_deref().unwrap_or(&self.problem_type))?;
+
+        if let Some(status) = self.status {
+            write!(f, " ({status})")?;
+        }
+
+        if let Some(detail) = &self.detail {
+            write!(f, ": {detail}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ProblemDetails {}
+
+// only application/problem+json, for a ByContentType that has to tell problems apart from the
+// api's other json
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProblemJson;
+
+impl Format for ProblemJson {
+    const NAME: &'static str = "problem+json";
+    const MEDIA_TYPES: &'static [&'static str] = &["application/problem+json"];
+
+    fn decode<T: DeserializeOwned>(body: &[u8]) -> Result<T, DecodeSource> {
+        Json::decode(body)
+    }
+}
+
+// ErrorForStatus for apis that answer errors with problem details: 4xx and 5xx responses become
+// a StatusError<ProblemDetails>, whose `error` is only Ok if the body really was problem+json
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProblemForStatus;
+
+impl Handler for ProblemForStatus {
+    type Input = Response;
+
+    type Output = Result<Response, StatusError<ProblemDetails>>;
+
+    async fn execute(
+        &self,
+        input: Self::Input,
+    ) -> Self::Output {
+        let status = input.status();
+        if !status.is_client_error() && !status.is_server_error() {
+            return Ok(input);
+        }
+
+        let headers = input.headers().clone();
+        let url = input.url().clone();
+
+        let is_problem = headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|media_type| ProblemJson::MEDIA_TYPES.iter().any(|m| m.eq_ignore_ascii_case(media_type.trim())));
+
+        let error = match is_problem {
+            true => decode_response(input, ProblemJson::NAME, ProblemJson::decode).await,
+            false => decode_response(input, ProblemJson::NAME, |_| Err("the response isn't application/problem+json")).await,
+        };
+
+        Err(StatusError { status, headers, url, error })
+    }
+}
@@ -0,0 +1,91 @@
+use std::{error::Error as StdError, fmt};
+
+use http::HeaderMap;
+use reqwest::{Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
+
+use super::{ByContentType, DecodeError};
+use crate::handlers::Handler;
+
+// a 4xx or 5xx response, with its body decoded into the api's error schema
+#[derive(Debug)]
+pub struct StatusError<E> {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub url: Url,
+    // Err when the body wasn't an E, e.g. a proxy's html error page
+    pub error: Result<E, DecodeError>,
+}
+
+impl<E> StatusError<E> {
+    pub fn is_client_error(&self) -> bool {
+        self.status.is_client_error()
+    }
+
+    pub fn is_server_error(&self) -> bool {
+        self.status.is_server_error()
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for StatusError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            Ok(error) => write!(f, "the server answered {} for {} with {error:?}", self.status, self.url),
+            Err(e) => write!(f, "the server answered {} for {}, and {e}", self.status, self.url),
+        }
+    }
+}
+
+impl<E: fmt::Debug> StdError for StatusError<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.error.as_ref().err().map(|e| e as _)
+    }
+}
+
+// lets 1xx-3xx responses through and turns 4xx and 5xx into a StatusError, like reqwest's
+// error_for_status; `~ErrorForStatus<E>` in chain!/pipe! bubbles it up so the next Handler only
+// sees responses worth decoding
+pub struct ErrorForStatus<E> {
+    decoder: ByContentType<E>,
+}
+
+impl<E> ErrorForStatus<E> {
+    // for error bodies in formats (or only the formats) that ByContentType::default doesn't have
+    pub fn with_decoder(decoder: ByContentType<E>) -> Self {
+        Self { decoder }
+    }
+}
+
+impl<E: DeserializeOwned> Default for ErrorForStatus<E> {
+    fn default() -> Self {
+        Self::with_decoder(ByContentType::default())
+    }
+}
+
+impl<E> fmt::Debug for ErrorForStatus<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorForStatus").field("decoder", &self.decoder).finish()
+    }
+}
+
+impl<E: Send> Handler for ErrorForStatus<E> {
+    type Input = Response;
+
+    type Output = Result<Response, StatusError<E>>;
+
+    async fn execute(
+        &self,
+        input: Self::Input,
+    ) -> Self::Output {
+        let status = input.status();
+        if !status.is_client_error() && !status.is_server_error() {
+            return Ok(input);
+        }
+
+        let headers = input.headers().clone();
+        let url = input.url().clone();
+        let error = self.decoder.execute(input).await;
+
+        Err(StatusError { status, headers, url, error })
+    }
+}
@@ -0,0 +1,82 @@
+use std::{any::type_name, fmt, marker::PhantomData};
+
+use derive_more::{Display, Error};
+use reqwest::{Response, StatusCode};
+use serde::de::DeserializeOwned;
+
+use super::{DecodeError, Format, Json};
+use crate::{handlers::Handler, net::net_error::NetError};
+
+// one error for everything that can go wrong between sending a request and getting a T out of it
+#[derive(Debug, Display, Error)]
+pub enum ResponseError<E> {
+    #[display("{_0}")]
+    Net(#[error(source)] NetError),
+
+    // the server answered with a non-2xx status, and its body was an E
+    #[display("the server answered {status} with {error:?}")]
+    Api {
+        status: StatusCode,
+        #[error(not(source))]
+        error: E,
+    },
+
+    #[display("{_0}")]
+    Decode(#[error(source)] DecodeError),
+}
+
+// decodes 2xx bodies into T and every other body into E; what #[endpoint(response = T, error = E)]
+// puts after BaseHandler
+pub struct TypedJson<T, E>(PhantomData<fn() -> (T, E)>);
+
+impl<T, E> TypedJson<T, E> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T, E> Default for TypedJson<T, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, E> fmt::Debug for TypedJson<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypedJson")
+            .field("response", &type_name::<T>())
+            .field("error", &type_name::<E>())
+            .finish()
+    }
+}
+
+impl<T, E> Handler for TypedJson<T, E>
+where
+    T: DeserializeOwned + Send,
+    E: DeserializeOwned + Send,
+{
+    type Input = Result<Response, NetError>;
+
+    type Output = Result<T, ResponseError<E>>;
+
+    async fn execute(
+        &self,
+        input: Self::Input,
+    ) -> Self::Output {
+        let response = input.map_err(ResponseError::Net)?;
+        let status = response.status();
+        let url = response.url().clone();
+        let body = response.bytes().await.map_err(|e| ResponseError::Net(e.into()))?;
+
+        let decode_error = |e| ResponseError::Decode(DecodeError::new(Json::NAME, status, url.clone(), &body, e));
+
+        if status.is_success() {
+            return Json::decode(&body).map_err(decode_error);
+        }
+
+        match Json::decode(&body) {
+            Ok(error) => Err(ResponseError::Api { status, error }),
+            Err(e) => Err(decode_error(e)),
+        }
+    }
+}
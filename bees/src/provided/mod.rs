@@ -1,4 +1,5 @@
 pub mod resources;
 pub mod capabilities;
 pub mod no_record;
-pub mod handlers;
\ No newline at end of file
+pub mod handlers;
+pub mod transports;
\ No newline at end of file
@@ -2,12 +2,14 @@
 use core::error;
 #[cfg(not(feature = "async-trait"))]
 use std::error;
-use std::{any::Any, fmt::Debug, future::ready, sync::Arc};
+#[cfg(not(feature = "async-trait"))]
+use std::future::ready;
+use std::{fmt::Debug, sync::Arc};
 
 use crate::resources::resource::Resource;
 
 #[cfg(feature = "async-trait")]
-use crate::resources::resource::{ResourceFuture, ResourceResult};
+use crate::resources::resource::ResourceResult;
 #[cfg(not(feature = "async-trait"))]
 use crate::resources::resource::ResourceOutput;
 
@@ -0,0 +1,241 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use derive_more::{Display, Error, From};
+use reqwest::{ResponseBuilderExt, Url};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+#[cfg(not(feature = "async-trait"))]
+use crate::net::transport::TransportOutput;
+use crate::net::transport::{Transport, TransportResult};
+
+#[derive(Debug, Display, Error, From)]
+#[display("CassetteError: {_variant}")]
+pub enum CassetteError {
+    #[display("couldn't read or write the cassette file: {_0}")]
+    Io(#[error(source)] std::io::Error),
+
+    #[display("couldn't (de)serialize the cassette: {_0}")]
+    Serde(#[error(source)] serde_json::Error),
+
+    #[display("the cassette contains a response that can't be rebuilt: {_0}")]
+    InvalidRecording(#[error(source)] http::Error),
+
+    #[from(skip)]
+    #[display("no recorded interaction matches {method} {url}")]
+    NoMatch { method: String, url: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CassetteBody {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl CassetteBody {
+    fn from_bytes(bytes: Vec<u8>) -> Self {
+        match String::from_utf8(bytes) {
+            Ok(text) => CassetteBody::Text(text),
+            Err(e) => CassetteBody::Bytes(e.into_bytes()),
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            CassetteBody::Text(text) => text.as_bytes(),
+            CassetteBody::Bytes(bytes) => bytes,
+        }
+    }
+}
+
+// request headers are deliberately left out, they're where credentials usually live
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    pub body: Option<CassetteBody>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: CassetteBody,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+impl RecordedRequest {
+    fn of(request: &reqwest::Request) -> Self {
+        Self {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            body: request
+                .body()
+                .and_then(|b| b.as_bytes())
+                .map(|b| CassetteBody::from_bytes(b.to_vec())),
+        }
+    }
+
+    fn matches(&self, other: &RecordedRequest) -> bool {
+        self.method == other.method
+            && self.url == other.url
+            && self.body.as_ref().map(CassetteBody::as_bytes) == other.body.as_ref().map(CassetteBody::as_bytes)
+    }
+}
+
+impl RecordedResponse {
+    fn to_response(&self, url: Url) -> TransportResult {
+        let mut builder = http::Response::builder().status(self.status).url(url);
+
+        for (name, value) in &self.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+
+        let response = builder
+            .body(reqwest::Body::from(self.body.as_bytes().to_vec()))
+            .map_err(CassetteError::from)?;
+
+        Ok(response.into())
+    }
+}
+
+enum Mode {
+    Record(Arc<dyn Transport>),
+    // `used` is parallel to the interactions; the same request made twice replays the
+    // recorded responses in order, and then keeps replaying the last one
+    Replay(Mutex<Vec<bool>>),
+}
+
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    interactions: Mutex<Vec<Interaction>>,
+}
+
+impl Cassette {
+    // starts from an empty cassette, overwriting whatever is at `path` on the first request
+    pub fn record<T: Transport + 'static>(path: impl AsRef<Path>, inner: T) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            mode: Mode::Record(Arc::new(inner)),
+            interactions: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn replay(path: impl AsRef<Path>) -> Result<Self, CassetteError> {
+        let interactions: Vec<Interaction> = serde_json::from_slice(&std::fs::read(path.as_ref())?)?;
+
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            mode: Mode::Replay(Mutex::new(vec![false; interactions.len()])),
+            interactions: Mutex::new(interactions),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self.mode, Mode::Record(_))
+    }
+
+    pub async fn interactions(&self) -> Vec<Interaction> {
+        self.interactions.lock().await.clone()
+    }
+
+    async fn handle(&self, request: reqwest::Request) -> TransportResult {
+        match &self.mode {
+            Mode::Record(inner) => self.record_one(inner, request).await,
+            Mode::Replay(used) => self.replay_one(used, request).await,
+        }
+    }
+
+    async fn record_one(&self, inner: &Arc<dyn Transport>, request: reqwest::Request) -> TransportResult {
+        let recorded_request = RecordedRequest::of(&request);
+
+        let response = inner.execute(request).await?;
+        let url = response.url().clone();
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(k, v)| Some((k.to_string(), v.to_str().ok()?.to_string())))
+            .collect();
+        let body = CassetteBody::from_bytes(response.bytes().await?.to_vec());
+
+        let recorded_response = RecordedResponse { status, headers, body };
+        let rebuilt = recorded_response.to_response(url);
+
+        // hold the lock while writing so concurrent requests can't interleave file writes
+        let mut interactions = self.interactions.lock().await;
+        interactions.push(Interaction {
+            request: recorded_request,
+            response: recorded_response,
+        });
+
+        let serialized = serde_json::to_vec_pretty(&*interactions).map_err(CassetteError::from)?;
+        tokio::fs::write(&self.path, serialized)
+            .await
+            .map_err(CassetteError::from)?;
+
+        rebuilt
+    }
+
+    async fn replay_one(&self, used: &Mutex<Vec<bool>>, request: reqwest::Request) -> TransportResult {
+        let wanted = RecordedRequest::of(&request);
+        let interactions = self.interactions.lock().await;
+        let mut used = used.lock().await;
+
+        let mut matching = interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| interaction.request.matches(&wanted));
+
+        let first = matching.clone().find(|(i, _)| !used[*i]);
+        let Some((i, interaction)) = first.or_else(|| matching.next_back()) else {
+            return Err(CassetteError::NoMatch {
+                method: wanted.method,
+                url: wanted.url,
+            }
+            .into());
+        };
+
+        used[i] = true;
+        interaction.response.to_response(request.url().clone())
+    }
+}
+
+impl fmt::Debug for Cassette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cassette")
+            .field("path", &self.path)
+            .field("recording", &self.is_recording())
+            .finish()
+    }
+}
+
+#[cfg(not(feature = "async-trait"))]
+impl Transport for Cassette {
+    fn execute<'a>(&'a self, request: reqwest::Request) -> TransportOutput<'a> {
+        TransportOutput::new(self.handle(request))
+    }
+}
+
+#[cfg(feature = "async-trait")]
+#[async_trait::async_trait]
+impl Transport for Cassette {
+    async fn execute(&self, request: reqwest::Request) -> TransportResult {
+        self.handle(request).await
+    }
+}
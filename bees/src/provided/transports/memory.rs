@@ -0,0 +1,146 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use http::{HeaderMap, StatusCode};
+use reqwest::{Method, Url};
+
+#[cfg(not(feature = "async-trait"))]
+use crate::net::transport::TransportOutput;
+use crate::net::transport::{Transport, TransportResult, response_from_parts};
+
+// Arcs so that a responder can be taken out of the table and called without holding its lock
+pub type Responder = Arc<dyn Fn(&reqwest::Request) -> http::Response<reqwest::Body> + Send + Sync>;
+
+struct MemoryRoute {
+    method: Method,
+    url: String,
+    responder: Responder,
+}
+
+// a copy of a request the MemoryTransport received, kept around so it can be inspected later
+#[derive(Debug, Clone)]
+pub struct MemoryRequest {
+    pub method: Method,
+    pub url: Url,
+    pub headers: HeaderMap,
+    pub body: Option<Vec<u8>>,
+}
+
+// serves responses out of a route table instead of the network. routes are matched on
+// method and full url (query included); anything unmatched gets a 404 unless a fallback is set
+#[derive(Default)]
+pub struct MemoryTransport {
+    routes: Mutex<Vec<MemoryRoute>>,
+    fallback: Mutex<Option<Responder>>,
+    received: Mutex<Vec<MemoryRequest>>,
+}
+
+impl MemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on<F>(&self, method: Method, url: impl AsRef<str>, responder: F) -> &Self
+    where
+        F: Fn(&reqwest::Request) -> http::Response<reqwest::Body> + Send + Sync + 'static,
+    {
+        self.routes.lock().unwrap().push(MemoryRoute {
+            method,
+            url: normalize(url.as_ref()),
+            responder: Arc::new(responder),
+        });
+        self
+    }
+
+    pub fn respond(&self, method: Method, url: impl AsRef<str>, status: u16, body: impl Into<Vec<u8>>) -> &Self {
+        let body = body.into();
+        self.on(method, url, move |_| {
+            http::Response::builder()
+                .status(status)
+                .body(reqwest::Body::from(body.clone()))
+                .expect("invalid status code passed to MemoryTransport::respond")
+        })
+    }
+
+    pub fn fallback<F>(&self, responder: F) -> &Self
+    where
+        F: Fn(&reqwest::Request) -> http::Response<reqwest::Body> + Send + Sync + 'static,
+    {
+        *self.fallback.lock().unwrap() = Some(Arc::new(responder));
+        self
+    }
+
+    pub fn received(&self) -> Vec<MemoryRequest> {
+        self.received.lock().unwrap().clone()
+    }
+
+    pub fn clear_received(&self) {
+        self.received.lock().unwrap().clear();
+    }
+
+    fn handle(&self, request: reqwest::Request) -> TransportResult {
+        self.received.lock().unwrap().push(MemoryRequest {
+            method: request.method().clone(),
+            url: request.url().clone(),
+            headers: request.headers().clone(),
+            body: request.body().and_then(|b| b.as_bytes()).map(<[u8]>::to_vec),
+        });
+
+        let url = request.url().as_str();
+
+        // cloned out first, so that a responder can add routes, and one that panics doesn't
+        // poison the table for every later request
+        let responder = {
+            let routes = self.routes.lock().unwrap();
+            match routes.iter().rev().find(|route| route.method == request.method() && route.url == url) {
+                Some(route) => Some(route.responder.clone()),
+                None => self.fallback.lock().unwrap().clone(),
+            }
+        };
+
+        let response = match responder {
+            Some(responder) => responder(&request),
+            None => http::Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(reqwest::Body::from(Vec::new()))
+                .unwrap(),
+        };
+
+        // so that Response::url() reports where the request was "sent" to
+        let (parts, body) = response.into_parts();
+        Ok(response_from_parts(parts, body, request.url().clone()))
+    }
+}
+
+// parse and re-serialize so that routes compare equal to what reqwest actually sends
+// (e.g. "https://example.com" vs "https://example.com/")
+fn normalize(url: &str) -> String {
+    Url::parse(url).map(String::from).unwrap_or_else(|_| url.to_string())
+}
+
+impl fmt::Debug for MemoryTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let routes = self.routes.lock().unwrap();
+        f.debug_struct("MemoryTransport")
+            .field("routes", &routes.iter().map(|r| (&r.method, &r.url)).collect::<Vec<_>>())
+            .field("received", &self.received.lock().unwrap().len())
+            .finish()
+    }
+}
+
+#[cfg(not(feature = "async-trait"))]
+impl Transport for MemoryTransport {
+    fn execute<'a>(&'a self, request: reqwest::Request) -> TransportOutput<'a> {
+        TransportOutput::new(std::future::ready(self.handle(request)))
+    }
+}
+
+#[cfg(feature = "async-trait")]
+#[async_trait::async_trait]
+impl Transport for MemoryTransport {
+    async fn execute(&self, request: reqwest::Request) -> TransportResult {
+        self.handle(request)
+    }
+}
@@ -0,0 +1,3 @@
+pub mod memory;
+#[cfg(feature = "cassette")]
+pub mod cassette;
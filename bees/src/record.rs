@@ -1,8 +1,82 @@
-use std::sync::Arc;
+use std::{any::TypeId, fmt, sync::Arc};
 
-use crate::capability::Capability;
+use crate::{
+    capability::Capability,
+    net::{concurrency::ConcurrencyLimiter, hosts::HostStrategy, rate_limiter::RateLimiter},
+};
 
 pub trait Record: Send {
     const SHARED_URL: &str;
     fn shared_caps() -> Arc<[Box<dyn Capability>]>;
-}
\ No newline at end of file
+
+    // a Record with a parent has its SHARED_URL appended to the parent's base url, and its
+    // capabilities applied after the parent's
+    fn parent() -> Option<RecordInfo> {
+        None
+    }
+
+    // enforced on top of the Client's own rate limiter for every Endpoint of this Record.
+    // called once per Client, which keeps the limiter for as long as it lives
+    fn rate_limiter() -> Option<Arc<RateLimiter>> {
+        None
+    }
+
+    // caps how many requests to this Record's Endpoints can be in flight at once, on top of
+    // the Client's own limit. called once per Client, like rate_limiter
+    fn concurrency_limiter() -> Option<Arc<ConcurrencyLimiter>> {
+        None
+    }
+
+    // other hosts serving the same api as SHARED_URL, which stays the primary one
+    fn mirrors() -> &'static [&'static str] {
+        &[]
+    }
+
+    fn host_strategy() -> HostStrategy {
+        HostStrategy::Failover
+    }
+}
+
+// what a Record provides, without its type, so that chains of parents can be walked at runtime
+#[derive(Clone, Copy)]
+pub struct RecordInfo {
+    pub type_id: fn() -> TypeId,
+    pub shared_url: &'static str,
+    pub shared_caps: fn() -> Arc<[Box<dyn Capability>]>,
+    pub parent: fn() -> Option<RecordInfo>,
+    pub mirrors: fn() -> &'static [&'static str],
+    pub host_strategy: fn() -> HostStrategy,
+}
+
+impl RecordInfo {
+    pub fn of<R: Record + 'static>() -> Self {
+        Self {
+            type_id: TypeId::of::<R>,
+            shared_url: R::SHARED_URL,
+            shared_caps: R::shared_caps,
+            parent: R::parent,
+            mirrors: R::mirrors,
+            host_strategy: R::host_strategy,
+        }
+    }
+
+    // this Record and all of its parents, root first
+    pub fn chain(self) -> Vec<RecordInfo> {
+        let mut chain = vec![self];
+        while let Some(parent) = (chain.last().unwrap().parent)() {
+            assert!(chain.len() < 64, "Record parents form a cycle");
+            chain.push(parent);
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+impl fmt::Debug for RecordInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordInfo")
+            .field("shared_url", &self.shared_url)
+            .field("parent", &(self.parent)())
+            .finish_non_exhaustive()
+    }
+}
@@ -1,6 +1,6 @@
 use std::{borrow::Borrow, fmt::Debug, hash::Hash, error::Error as StdError};
 // #[cfg(not(feature = "async-trait"))]
-use std::{any::Any, sync::Arc};
+use std::sync::Arc;
 #[cfg(not(feature = "async-trait"))]
 use std::pin::Pin;
 
@@ -1,5 +1,3 @@
-use std::convert::Infallible;
-
 use derive_more::{Display, Error, From};
 
 use crate::{capability::CapError, endpoint::HandlerStackError, net::net_error::NetError, utils::resource_string::FormatStringError};
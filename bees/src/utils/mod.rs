@@ -1,2 +1,3 @@
 pub mod error;
-pub mod resource_string;
\ No newline at end of file
+pub mod resource_string;
+pub(crate) mod random;
\ No newline at end of file
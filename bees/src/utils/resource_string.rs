@@ -1,4 +1,4 @@
-use std::{any::Any, sync::{Arc, Weak}, error::Error as StdError};
+use std::{sync::{Arc, Weak}, error::Error as StdError};
 use derive_more::{Error, Display, From};
 use crate::{net::Client, resources::resource_handler::ResourceManager};
 
@@ -0,0 +1,54 @@
+use std::{
+    sync::{Arc, Weak, mpsc},
+    time::Duration,
+};
+
+use bees::{net::transport::Transport, provided::transports::memory::MemoryTransport};
+use reqwest::Method;
+
+fn get(url: &str) -> reqwest::Request {
+    reqwest::Client::new().get(url).build().unwrap()
+}
+
+fn ok() -> http::Response<reqwest::Body> {
+    http::Response::new(reqwest::Body::from("ok"))
+}
+
+#[tokio::test]
+async fn a_responder_can_add_routes() {
+    let transport = Arc::new(MemoryTransport::new());
+    let weak: Weak<MemoryTransport> = Arc::downgrade(&transport);
+
+    transport.on(Method::GET, "https://memory.test/first", move |_| {
+        weak.upgrade().unwrap().on(Method::GET, "https://memory.test/second", |_| ok());
+        ok()
+    });
+
+    // on a thread of its own, since a deadlock on the route table would block whatever runs it
+    let (sender, receiver) = mpsc::channel();
+    let first = transport.clone();
+    std::thread::spawn(move || {
+        let response = futures::executor::block_on(first.execute(get("https://memory.test/first")));
+        let _ = sender.send(response.map(|response| response.status()));
+    });
+
+    let first = receiver.recv_timeout(Duration::from_secs(5)).expect("the responder deadlocked on the route table");
+    assert_eq!(first.unwrap(), 200);
+
+    let second = transport.execute(get("https://memory.test/second")).await.unwrap();
+    assert_eq!(second.status(), 200);
+}
+
+#[tokio::test]
+async fn a_panicking_responder_doesnt_break_later_requests() {
+    let transport = Arc::new(MemoryTransport::new());
+    transport.on(Method::GET, "https://memory.test/panics", |_| panic!("responder panicked"));
+    transport.on(Method::GET, "https://memory.test/fine", |_| ok());
+
+    let panicking = transport.clone();
+    let panicked = tokio::spawn(async move { panicking.execute(get("https://memory.test/panics")).await }).await;
+    assert!(panicked.is_err());
+
+    let fine = transport.execute(get("https://memory.test/fine")).await.unwrap();
+    assert_eq!(fine.status(), 200);
+}
@@ -0,0 +1,81 @@
+use std::{sync::Arc, time::Duration};
+
+use bees::net::rate_limiter::RateLimiter;
+
+// a waiter reads the clock again after letting go of the lock, and that used to underflow when
+// the clock had already passed its slot: a panic in debug builds, a ~584 year sleep in release
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_acquires_at_a_high_rate_all_get_through() {
+    let limiter = Arc::new(RateLimiter::new(1_000_000.0, 1));
+
+    let tasks = (0..64)
+        .map(|_| {
+            let limiter = limiter.clone();
+            tokio::spawn(async move {
+                for _ in 0..50 {
+                    limiter.acquire().await;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let all = async {
+        for task in tasks {
+            task.await.expect("an acquiring task panicked");
+        }
+    };
+
+    tokio::time::timeout(Duration::from_secs(10), all)
+        .await
+        .expect("acquires got stuck");
+}
+
+// slots only a few hundred nanoseconds apart, so that some of them go by in between the lock
+// and the second clock read; sweeping the rate makes that happen on any machine
+#[tokio::test]
+async fn slots_that_pass_before_the_sleep_starts_dont_underflow() {
+    let sweep = async {
+        for rate in (4..=400).map(|i| i as f64 * 1e5) {
+            let limiter = RateLimiter::new(rate, 1);
+            for _ in 0..1000 {
+                limiter.acquire().await;
+            }
+        }
+    };
+
+    tokio::time::timeout(Duration::from_secs(30), sweep)
+        .await
+        .expect("acquires got stuck");
+}
+
+// a saved window that refilled while the process was down has to come back with its whole
+// burst, not just the one token a window that ran dry right at startup would have
+#[tokio::test]
+async fn a_reloaded_rate_window_gets_its_refilled_burst_back() {
+    use bees::net::rate_limiter::Window;
+
+    let path = std::env::temp_dir().join(format!("bees-rate-limiter-{}", std::process::id()));
+    let limiter = || RateLimiter::with_windows([Window::rate(10.0, 5).persistent()]).persist_to(&path).unwrap();
+
+    let first = limiter();
+    for _ in 0..5 {
+        first.acquire().await;
+    }
+    drop(first);
+
+    tokio::time::sleep(Duration::from_millis(600)).await;
+
+    let second = limiter();
+    let started = std::time::Instant::now();
+    for _ in 0..5 {
+        second.acquire().await;
+    }
+    let waited = started.elapsed();
+
+    drop(second);
+    let _ = std::fs::remove_file(&path);
+
+    // one token at a time would take 400ms
+    assert!(waited < Duration::from_millis(200), "waited {waited:?} for a refilled burst");
+}
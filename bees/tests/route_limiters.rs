@@ -0,0 +1,108 @@
+use std::{
+    sync::{Arc, atomic::{AtomicUsize, Ordering}},
+    time::{Duration, Instant},
+};
+
+use bees::{
+    capability::Capability,
+    endpoint::{EndpointInfo, HandlerStack, HandlerStackError},
+    handlers::BaseHandler,
+    net::{
+        Client, HttpMethod, HttpVerb,
+        concurrency::ConcurrencyLimiter,
+        net_error::NetError,
+        rate_limiter::{RateLimiter, Window},
+    },
+    provided::transports::memory::MemoryTransport,
+    record::Record,
+};
+use reqwest::Response;
+
+fn persisted_at() -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("bees-route-limiters-{}", std::process::id()))
+}
+
+static LIMITERS_MADE: AtomicUsize = AtomicUsize::new(0);
+static CONCURRENCY_LIMITERS_MADE: AtomicUsize = AtomicUsize::new(0);
+
+struct Tenants;
+
+impl Record for Tenants {
+    const SHARED_URL: &str = "https://tenants.test/";
+
+    fn shared_caps() -> Arc<[Box<dyn Capability>]> {
+        Arc::new([])
+    }
+
+    fn rate_limiter() -> Option<Arc<RateLimiter>> {
+        LIMITERS_MADE.fetch_add(1, Ordering::Relaxed);
+        let limiter = RateLimiter::with_windows([Window::rate(1.0, 1).persistent()]);
+        Some(Arc::new(limiter.persist_to(persisted_at()).unwrap()))
+    }
+
+    fn concurrency_limiter() -> Option<Arc<ConcurrencyLimiter>> {
+        CONCURRENCY_LIMITERS_MADE.fetch_add(1, Ordering::Relaxed);
+        Some(Arc::new(ConcurrencyLimiter::new(1)))
+    }
+}
+
+#[derive(Debug)]
+struct Ping;
+
+impl EndpointInfo for Ping {
+    type Record = Tenants;
+    type CallContext = ();
+
+    const PATH: &str = "ping";
+
+    fn capabilities(_: &mut Self::CallContext) -> Arc<[Box<dyn Capability>]> {
+        Arc::new([])
+    }
+
+    async fn http_method(_: &mut Self::CallContext) -> HttpMethod {
+        HttpMethod::new_no_body(HttpVerb::GET)
+    }
+}
+
+impl HandlerStack<Result<Response, NetError>> for Ping {
+    type Handlers = BaseHandler;
+
+    async fn handlers(_: &mut Self::CallContext) -> Result<Self::Handlers, HandlerStackError> {
+        Ok(BaseHandler)
+    }
+}
+
+fn client() -> Client {
+    let transport = MemoryTransport::new();
+    transport.respond(reqwest::Method::GET, "https://tenants.test/ping", 200, "pong");
+    Client::new_with_transport(transport, RateLimiter::new(1000.0, 1000))
+}
+
+// one test, since both Clients' limiters persist to the same file
+#[tokio::test]
+async fn every_client_gets_its_own_record_limiter_and_saves_it_on_drop() {
+    let _ = std::fs::remove_file(persisted_at());
+
+    let first = client();
+    let second = client();
+
+    first.run_endpoint::<Ping, Result<Response, NetError>>().await.unwrap().unwrap();
+    assert_eq!(LIMITERS_MADE.load(Ordering::Relaxed), 1);
+
+    // a limiter shared with `first` would have no token left for the next second
+    let started = Instant::now();
+    second.run_endpoint::<Ping, Result<Response, NetError>>().await.unwrap().unwrap();
+    let waited = started.elapsed();
+    assert!(waited < Duration::from_millis(500), "waited {waited:?} on another Client's quota");
+
+    // and each Client keeps the one it made
+    first.clone().run_endpoint::<Ping, Result<Response, NetError>>().await.unwrap().unwrap();
+    assert_eq!(LIMITERS_MADE.load(Ordering::Relaxed), 2);
+    assert_eq!(CONCURRENCY_LIMITERS_MADE.load(Ordering::Relaxed), 2);
+
+    drop(second);
+    std::fs::remove_file(persisted_at()).expect("dropping the Client didn't save its limiter");
+
+    drop(first);
+    std::fs::remove_file(persisted_at()).expect("dropping the Client didn't save its limiter");
+}